@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP TABLE security_daily_bar;
\ No newline at end of file
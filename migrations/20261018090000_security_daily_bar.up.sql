@@ -0,0 +1,44 @@
+-- Your SQL goes here
+CREATE TABLE security_daily_bar (
+    row_id varchar not null default uuid_generate_v4(),
+    open_date_year varchar not null default '',
+    open_date_month varchar not null default '',
+    open_date_day varchar not null default '',
+    security_code varchar not null default '',
+    market_type varchar not null default '',
+    price_date varchar not null default '',
+    trade_volume numeric not null default 0,
+    trade_value numeric not null default 0,
+    price_open numeric not null default 0,
+    price_high numeric not null default 0,
+    price_low numeric not null default 0,
+    price_close numeric not null default 0,
+    price_change numeric not null default 0,
+    trade_count numeric not null default 0,
+    created_date timestamp not null default now(),
+    updated_date timestamp not null default now(),
+    CONSTRAINT security_daily_bar_key PRIMARY KEY (row_id)
+);
+
+CREATE UNIQUE INDEX security_daily_bar_price_date_idx ON security_daily_bar USING btree (security_code, price_date);
+CREATE INDEX security_daily_bar_open_date_idx ON security_daily_bar USING btree (open_date_year, open_date_month, open_date_day);
+
+COMMENT ON TABLE security_daily_bar IS '每日交易資料';
+
+COMMENT ON COLUMN security_daily_bar.row_id IS '序號';
+COMMENT ON COLUMN security_daily_bar.open_date_year IS '開市日期_年';
+COMMENT ON COLUMN security_daily_bar.open_date_month IS '開市日期_月';
+COMMENT ON COLUMN security_daily_bar.open_date_day IS '開市日期_日';
+COMMENT ON COLUMN security_daily_bar.security_code IS '證券代碼';
+COMMENT ON COLUMN security_daily_bar.market_type IS '市場別';
+COMMENT ON COLUMN security_daily_bar.price_date IS '交易日期';
+COMMENT ON COLUMN security_daily_bar.trade_volume IS '成交股數';
+COMMENT ON COLUMN security_daily_bar.trade_value IS '成交金額';
+COMMENT ON COLUMN security_daily_bar.price_open IS '開盤價';
+COMMENT ON COLUMN security_daily_bar.price_high IS '最高價';
+COMMENT ON COLUMN security_daily_bar.price_low IS '最低價';
+COMMENT ON COLUMN security_daily_bar.price_close IS '收盤價';
+COMMENT ON COLUMN security_daily_bar.price_change IS '漲跌價差';
+COMMENT ON COLUMN security_daily_bar.trade_count IS '成交筆數';
+COMMENT ON COLUMN security_daily_bar.created_date IS '新增日期';
+COMMENT ON COLUMN security_daily_bar.updated_date IS '修改日期';
@@ -0,0 +1,10 @@
+-- Add down migration script here
+ALTER TABLE security_daily_bar DROP COLUMN trade_date;
+ALTER TABLE security_daily_bar DROP COLUMN open_date;
+ALTER TABLE security_price DROP COLUMN trade_date;
+ALTER TABLE security_price DROP COLUMN open_date;
+ALTER TABLE security_task DROP COLUMN open_date;
+ALTER TABLE security_temp DROP COLUMN open_date;
+ALTER TABLE response_data DROP COLUMN open_date;
+ALTER TABLE daily_task DROP COLUMN open_date;
+ALTER TABLE calendar_data DROP COLUMN ce_date;
@@ -0,0 +1,53 @@
+-- Your SQL goes here
+ALTER TABLE calendar_data ADD COLUMN ce_date date;
+UPDATE calendar_data SET ce_date = make_date(ce_year::integer, ce_month::integer, ce_day::integer);
+ALTER TABLE calendar_data ALTER COLUMN ce_date SET NOT NULL;
+CREATE INDEX calendar_data_ce_date_idx ON calendar_data USING btree (ce_date);
+COMMENT ON COLUMN calendar_data.ce_date IS '西元日期';
+
+ALTER TABLE daily_task ADD COLUMN open_date date;
+UPDATE daily_task SET open_date = make_date(open_date_year::integer, open_date_month::integer, open_date_day::integer);
+ALTER TABLE daily_task ALTER COLUMN open_date SET NOT NULL;
+CREATE INDEX daily_task_open_date_dt_idx ON daily_task USING btree (open_date);
+COMMENT ON COLUMN daily_task.open_date IS '開市日期';
+
+ALTER TABLE response_data ADD COLUMN open_date date;
+UPDATE response_data SET open_date = make_date(open_date_year::integer, open_date_month::integer, open_date_day::integer);
+ALTER TABLE response_data ALTER COLUMN open_date SET NOT NULL;
+CREATE INDEX response_data_open_date_dt_idx ON response_data USING btree (exec_code, open_date);
+COMMENT ON COLUMN response_data.open_date IS '開市日期';
+
+ALTER TABLE security_temp ADD COLUMN open_date date;
+UPDATE security_temp SET open_date = make_date(open_date_year::integer, open_date_month::integer, open_date_day::integer);
+ALTER TABLE security_temp ALTER COLUMN open_date SET NOT NULL;
+CREATE INDEX security_temp_open_date_dt_idx ON security_temp USING btree (open_date);
+COMMENT ON COLUMN security_temp.open_date IS '開市日期';
+
+ALTER TABLE security_task ADD COLUMN open_date date;
+UPDATE security_task SET open_date = make_date(open_date_year::integer, open_date_month::integer, open_date_day::integer);
+ALTER TABLE security_task ALTER COLUMN open_date SET NOT NULL;
+CREATE INDEX security_task_open_date_dt_idx ON security_task USING btree (open_date);
+COMMENT ON COLUMN security_task.open_date IS '開市日期';
+
+ALTER TABLE security_price ADD COLUMN open_date date;
+ALTER TABLE security_price ADD COLUMN trade_date date;
+UPDATE security_price SET open_date = make_date(open_date_year::integer, open_date_month::integer, open_date_day::integer);
+UPDATE security_price
+   SET trade_date = make_date(split_part(price_date, '/', 1)::integer + 1911, split_part(price_date, '/', 2)::integer, split_part(price_date, '/', 3)::integer)
+ WHERE price_date ~ '^[0-9]{2,4}/[0-9]{2}/[0-9]{2}$';
+ALTER TABLE security_price ALTER COLUMN open_date SET NOT NULL;
+CREATE INDEX security_price_open_date_dt_idx ON security_price USING btree (open_date);
+CREATE INDEX security_price_trade_date_idx ON security_price USING btree (security_code, trade_date);
+COMMENT ON COLUMN security_price.open_date IS '開市日期';
+COMMENT ON COLUMN security_price.trade_date IS '收盤日期 (西元)';
+
+ALTER TABLE security_daily_bar ADD COLUMN open_date date;
+ALTER TABLE security_daily_bar ADD COLUMN trade_date date;
+UPDATE security_daily_bar SET open_date = make_date(open_date_year::integer, open_date_month::integer, open_date_day::integer);
+UPDATE security_daily_bar
+   SET trade_date = make_date(split_part(price_date, '/', 1)::integer + 1911, split_part(price_date, '/', 2)::integer, split_part(price_date, '/', 3)::integer);
+ALTER TABLE security_daily_bar ALTER COLUMN open_date SET NOT NULL;
+ALTER TABLE security_daily_bar ALTER COLUMN trade_date SET NOT NULL;
+CREATE INDEX security_daily_bar_trade_date_idx ON security_daily_bar USING btree (security_code, trade_date);
+COMMENT ON COLUMN security_daily_bar.open_date IS '開市日期';
+COMMENT ON COLUMN security_daily_bar.trade_date IS '交易日期 (西元)';
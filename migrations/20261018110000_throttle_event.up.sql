@@ -0,0 +1,28 @@
+-- Your SQL goes here
+CREATE TABLE throttle_event (
+    row_id varchar not null default uuid_generate_v4(),
+    host varchar not null default '',
+    market_type varchar not null default '',
+    security_code varchar not null default '',
+    open_date date not null,
+    reason varchar not null default '',
+    backoff_seconds integer not null default 0,
+    created_date timestamp not null default now(),
+    updated_date timestamp not null default now(),
+    CONSTRAINT throttle_event_key PRIMARY KEY (row_id)
+);
+
+CREATE INDEX throttle_event_host_idx ON throttle_event USING btree (host, created_date);
+CREATE INDEX throttle_event_open_date_idx ON throttle_event USING btree (open_date);
+
+COMMENT ON TABLE throttle_event IS '流量限制紀錄';
+
+COMMENT ON COLUMN throttle_event.row_id IS '序號';
+COMMENT ON COLUMN throttle_event.host IS '來源主機';
+COMMENT ON COLUMN throttle_event.market_type IS '市場別';
+COMMENT ON COLUMN throttle_event.security_code IS '證券代碼';
+COMMENT ON COLUMN throttle_event.open_date IS '開市日期';
+COMMENT ON COLUMN throttle_event.reason IS '限制原因';
+COMMENT ON COLUMN throttle_event.backoff_seconds IS '暫停秒數';
+COMMENT ON COLUMN throttle_event.created_date IS '新增日期';
+COMMENT ON COLUMN throttle_event.updated_date IS '修改日期';
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX security_task_exec_status_idx;
+ALTER TABLE security_task DROP COLUMN last_error;
+ALTER TABLE security_task DROP COLUMN retry_count;
+ALTER TABLE security_task DROP COLUMN exec_status;
\ No newline at end of file
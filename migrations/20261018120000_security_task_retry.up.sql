@@ -0,0 +1,10 @@
+-- Your SQL goes here
+ALTER TABLE security_task ADD COLUMN exec_status varchar not null default 'WAIT';
+ALTER TABLE security_task ADD COLUMN retry_count integer not null default 0;
+ALTER TABLE security_task ADD COLUMN last_error varchar not null default '';
+UPDATE security_task SET exec_status = 'DONE' WHERE is_enabled = 0;
+CREATE INDEX security_task_exec_status_idx ON security_task USING btree (exec_status);
+
+COMMENT ON COLUMN security_task.exec_status IS '執行狀態：等待:WAIT/完成:DONE/失敗:FAILED';
+COMMENT ON COLUMN security_task.retry_count IS '重試次數';
+COMMENT ON COLUMN security_task.last_error IS '最後錯誤';
@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE daily_task DROP COLUMN last_error;
+
+COMMENT ON COLUMN daily_task.exec_status IS '執行狀態：等待:WAIT/開始:OPEN/執行:EXEC/結束:EXIT/停止:STOP';
\ No newline at end of file
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE daily_task ADD COLUMN last_error varchar not null default '';
+
+COMMENT ON COLUMN daily_task.exec_status IS '執行狀態：等待:WAIT/開始:OPEN/執行:EXEC/結束:EXIT/停止:STOP/失敗:FAIL';
+COMMENT ON COLUMN daily_task.last_error IS '最後錯誤';
@@ -0,0 +1,27 @@
+-- Your SQL goes here
+CREATE TABLE task_dependency (
+    row_id varchar not null default uuid_generate_v4(),
+    job_code varchar not null default '',
+    depend_code varchar not null default '',
+    created_date timestamp not null default now(),
+    updated_date timestamp not null default now(),
+    CONSTRAINT task_dependency_key PRIMARY KEY (row_id),
+    CONSTRAINT task_dependency_job_code_uk UNIQUE (job_code, depend_code)
+);
+
+CREATE INDEX task_dependency_depend_code_idx ON task_dependency USING btree (depend_code);
+
+INSERT INTO task_dependency(job_code, depend_code)
+VALUES ('get_web_security', 'delete_temp')
+     , ('res_to_temp', 'get_web_security')
+     , ('temp_to_task', 'res_to_temp')
+     , ('task_run', 'temp_to_task')
+     , ('price_value', 'res_price');
+
+COMMENT ON TABLE task_dependency IS '工作相依表';
+
+COMMENT ON COLUMN task_dependency.row_id IS '序號';
+COMMENT ON COLUMN task_dependency.job_code IS '工作代碼';
+COMMENT ON COLUMN task_dependency.depend_code IS '相依工作代碼 (同一開市日需先結束)';
+COMMENT ON COLUMN task_dependency.created_date IS '新增日期';
+COMMENT ON COLUMN task_dependency.updated_date IS '修改日期';
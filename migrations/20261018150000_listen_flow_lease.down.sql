@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP INDEX listen_flow_lease_until_idx;
+DROP INDEX listen_flow_flow_month_uk;
+
+ALTER TABLE listen_flow DROP COLUMN heartbeat_date;
+ALTER TABLE listen_flow DROP COLUMN lease_until;
+ALTER TABLE listen_flow DROP COLUMN owner;
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+ALTER TABLE listen_flow ADD COLUMN owner varchar not null default '';
+ALTER TABLE listen_flow ADD COLUMN lease_until timestamp not null default now();
+ALTER TABLE listen_flow ADD COLUMN heartbeat_date timestamp not null default now();
+
+DELETE FROM listen_flow a
+ USING listen_flow b
+ WHERE a.flow_code = b.flow_code
+   AND a.flow_param1 IS NOT DISTINCT FROM b.flow_param1
+   AND a.flow_param2 IS NOT DISTINCT FROM b.flow_param2
+   AND (a.created_date, a.row_id) < (b.created_date, b.row_id);
+
+CREATE UNIQUE INDEX listen_flow_flow_month_uk ON listen_flow USING btree (flow_code, flow_param1, flow_param2);
+CREATE INDEX listen_flow_lease_until_idx ON listen_flow USING btree (pstatus, lease_until);
+
+COMMENT ON COLUMN listen_flow.owner IS '執行者 (主機:線程ID:啟動時間)';
+COMMENT ON COLUMN listen_flow.lease_until IS '租約到期時間';
+COMMENT ON COLUMN listen_flow.heartbeat_date IS '最後心跳時間';
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX security_task_claimed_idx;
+
+ALTER TABLE security_task DROP COLUMN claimed_at;
+ALTER TABLE security_task DROP COLUMN claimed_by;
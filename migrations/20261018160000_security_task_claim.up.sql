@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER TABLE security_task ADD COLUMN claimed_by varchar not null default '';
+ALTER TABLE security_task ADD COLUMN claimed_at timestamp;
+CREATE INDEX security_task_claimed_idx ON security_task USING btree (claimed_by, claimed_at);
+
+COMMENT ON COLUMN security_task.claimed_by IS '認領者 (主機:線程ID:啟動時間，空白為未認領)';
+COMMENT ON COLUMN security_task.claimed_at IS '認領時間 (處理結束時更新為結束時間)';
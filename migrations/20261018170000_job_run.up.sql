@@ -0,0 +1,40 @@
+-- Your SQL goes here
+CREATE TABLE job_run (
+    row_id varchar not null default uuid_generate_v4(),
+    flow_code varchar not null default '',
+    job_code varchar not null default '',
+    open_date date not null,
+    owner varchar not null default '',
+    start_time timestamp not null default now(),
+    end_time timestamp,
+    exec_status varchar not null default 'OPEN',
+    insert_count bigint not null default 0,
+    update_count bigint not null default 0,
+    skip_count bigint not null default 0,
+    request_count bigint not null default 0,
+    last_error varchar not null default '',
+    created_date timestamp not null default now(),
+    updated_date timestamp not null default now(),
+    CONSTRAINT job_run_key PRIMARY KEY (row_id)
+);
+
+CREATE INDEX job_run_start_time_idx ON job_run USING btree (start_time);
+CREATE INDEX job_run_job_code_idx ON job_run USING btree (job_code, open_date);
+
+COMMENT ON TABLE job_run IS '工作執行歷程';
+
+COMMENT ON COLUMN job_run.row_id IS '序號';
+COMMENT ON COLUMN job_run.flow_code IS '流程代碼';
+COMMENT ON COLUMN job_run.job_code IS '工作代碼';
+COMMENT ON COLUMN job_run.open_date IS '開市日期';
+COMMENT ON COLUMN job_run.owner IS '執行者 (主機:線程ID:啟動時間)';
+COMMENT ON COLUMN job_run.start_time IS '開始時間';
+COMMENT ON COLUMN job_run.end_time IS '結束時間';
+COMMENT ON COLUMN job_run.exec_status IS '執行狀態：執行中:OPEN/結束:EXIT/失敗:FAIL';
+COMMENT ON COLUMN job_run.insert_count IS '新增筆數';
+COMMENT ON COLUMN job_run.update_count IS '修改筆數';
+COMMENT ON COLUMN job_run.skip_count IS '略過筆數';
+COMMENT ON COLUMN job_run.request_count IS '網路請求次數';
+COMMENT ON COLUMN job_run.last_error IS '錯誤訊息';
+COMMENT ON COLUMN job_run.created_date IS '新增日期';
+COMMENT ON COLUMN job_run.updated_date IS '修改日期';
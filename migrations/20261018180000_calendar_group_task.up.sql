@@ -0,0 +1,2 @@
+-- Your SQL goes here
+UPDATE calendar_data SET group_task = 'FIRST' WHERE group_task = 'FRIST';
@@ -8,26 +8,26 @@ use crate::repository::Repository;
 
 use super::model::CalendarData;
 
-pub async fn create(data: CalendarData) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn create(repo: &Repository, data: CalendarData) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
         INSERT INTO calendar_data(
-            ce_year, ce_month, ce_day, week_index, date_status, group_task, created_date, updated_date
-        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8 )
+            ce_year, ce_month, ce_day, ce_date, week_index, date_status, group_task, created_date, updated_date
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9 )
     ",
     )
     .bind(data.ce_year)
     .bind(data.ce_month)
     .bind(data.ce_day)
+    .bind(data.ce_date)
     .bind(data.week_index)
     .bind(data.date_status)
     .bind(data.group_task)
     .bind(Local::now())
     .bind(Local::now())
-    .execute(&conn)
+    .execute(conn)
     .await
     {
         Ok(cnt) => Ok(cnt.rows_affected()),
@@ -35,9 +35,39 @@ pub async fn create(data: CalendarData) -> Result<u64, sqlx::Error> {
     }
 }
 
-pub async fn find_one(q_year: &str, q_month: &str, q_day: &str) -> Option<CalendarData> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn modify(repo: &Repository, data: CalendarData) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        UPDATE calendar_data
+           SET week_index = $1
+             , date_status = $2
+             , group_task = $3
+             , updated_date = $4
+         WHERE row_id = $5
+    ",
+    )
+    .bind(data.week_index)
+    .bind(data.date_status)
+    .bind(data.group_task)
+    .bind(Local::now())
+    .bind(data.row_id)
+    .execute(conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn find_one(
+    repo: &Repository,
+    q_year: &str,
+    q_month: &str,
+    q_day: &str,
+) -> Option<CalendarData> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -45,6 +75,7 @@ pub async fn find_one(q_year: &str, q_month: &str, q_day: &str) -> Option<Calend
              , ce_year
              , ce_month
              , ce_day
+             , ce_date
              , week_index
              , date_status
              , group_task
@@ -62,11 +93,12 @@ pub async fn find_one(q_year: &str, q_month: &str, q_day: &str) -> Option<Calend
         ce_year: row.get("ce_year"),
         ce_month: row.get("ce_month"),
         ce_day: row.get("ce_day"),
+        ce_date: row.get("ce_date"),
         date_status: row.get("date_status"),
         group_task: row.get("group_task"),
         week_index: row.get("week_index"),
     })
-    .fetch_optional(&conn)
+    .fetch_optional(conn)
     .await
     {
         Ok(row) => row,
@@ -76,3 +108,48 @@ pub async fn find_one(q_year: &str, q_month: &str, q_day: &str) -> Option<Calend
         }
     }
 }
+
+/// 取得年度行事曆
+///
+/// # Errors
+/// 資料庫讀取失敗時回傳 `sqlx::Error`
+pub async fn find_all_by_year(
+    repo: &Repository,
+    q_year: i32,
+) -> Result<Vec<CalendarData>, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        SELECT row_id
+             , ce_year
+             , ce_month
+             , ce_day
+             , ce_date
+             , week_index
+             , date_status
+             , group_task
+          FROM calendar_data
+         WHERE ce_date >= make_date($1, 1, 1)
+           AND ce_date < make_date($1 + 1, 1, 1)
+         ORDER BY ce_date
+    ",
+    )
+    .bind(q_year)
+    .map(|row: PgRow| CalendarData {
+        row_id: row.get("row_id"),
+        ce_year: row.get("ce_year"),
+        ce_month: row.get("ce_month"),
+        ce_day: row.get("ce_day"),
+        ce_date: row.get("ce_date"),
+        date_status: row.get("date_status"),
+        group_task: row.get("group_task"),
+        week_index: row.get("week_index"),
+    })
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => Ok(rows),
+        Err(e) => Err(e),
+    }
+}
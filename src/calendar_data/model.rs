@@ -1,11 +1,17 @@
 #![warn(clippy::all, clippy::pedantic)]
 
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::roc_date::RocDate;
+
 #[derive(Debug, Clone)]
 pub struct CalendarData {
     pub row_id: String,
     pub ce_year: String,
     pub ce_month: String,
     pub ce_day: String,
+    pub ce_date: NaiveDate,
     pub week_index: i32,
     pub date_status: String,
     pub group_task: String,
@@ -15,21 +21,58 @@ impl std::fmt::Display for CalendarData {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         let row_id = self.row_id.clone();
         let ce_year = self.ce_year.clone();
-        let tw_year = ce_year.parse::<i32>().unwrap() - 1911;
         let ce_month = self.ce_month.clone();
         let ce_day = self.ce_day.clone();
+        let tw_date = RocDate::from(self.ce_date);
         let date_status = self.date_status.clone();
         let group_task = self.group_task.clone();
 
         write!(
             f,
-            r#"{0}, 
-            ce_date: {1}/{3}/{4}, 
-            tw_date: {2}/{3}/{4}, 
-            date_status: {5},
-            group_task: {6}
-            "#,
-            row_id, ce_year, tw_year, ce_month, ce_day, date_status, group_task
+            r"{row_id}, 
+            ce_date: {ce_year}/{ce_month}/{ce_day}, 
+            tw_date: {tw_date}, 
+            date_status: {date_status},
+            group_task: {group_task}
+            "
+        )
+    }
+}
+
+/// 市場開休市日期 (TWSE holidaySchedule)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HolidaySchedule {
+    pub stat: String,
+    pub date: Option<String>,
+    pub title: Option<String>,
+    pub fields: Option<Vec<String>>,
+    pub data: Option<Vec<Vec<String>>>,
+}
+
+/// 開休市日期
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HolidayDate {
+    pub ce_date: NaiveDate,
+    pub name: String,
+    pub description: String,
+    /// 開市:O/休市:S
+    pub date_status: String,
+}
+
+impl std::fmt::Display for HolidayDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let ce_date = self.ce_date;
+        let name = self.name.clone();
+        let description = self.description.clone();
+        let date_status = self.date_status.clone();
+
+        write!(
+            f,
+            r"ce_date: {ce_date}, 
+            name: {name}, 
+            description: {description}, 
+            date_status: {date_status}
+            "
         )
     }
 }
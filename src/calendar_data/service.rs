@@ -1,10 +1,27 @@
 #![warn(clippy::all, clippy::pedantic)]
 
+use std::str::FromStr;
+
 use chrono::{Datelike, Local, NaiveDate};
+use tokio_retry::Retry;
+use tracing::{event, Level};
+
+use crate::{
+    config, market_source,
+    repository::Repository,
+    response_data,
+    roc_date::RocDate,
+    security_price::{self, model::SecurityPrice},
+    Error,
+};
 
-use crate::security_price::{self, model::SecurityPrice};
+use super::{
+    dao,
+    model::{CalendarData, HolidayDate, HolidaySchedule},
+};
 
-use super::{dao, model::CalendarData};
+/// 開市說明 (其餘列於表中者皆為休市)
+const OPEN_KEYWORDS: [&str; 5] = ["開始交易", "最後交易", "補行交易", "照常交易", "照常開市"];
 
 ///
 /// 取得每個月的最後一天
@@ -33,24 +50,22 @@ fn get_weekday(year: i32, month: u32, day: u32) -> i32 {
 ///
 /// (年，月，日，開市第幾天)
 ///
-async fn get_open_stock_month(year: i32, last_price_date: &str) -> Vec<(i32, u32, u32, i32)> {
+async fn get_open_stock_month(
+    repo: &Repository,
+    year: i32,
+    last_price_date: NaiveDate,
+) -> Vec<(i32, u32, u32, i32)> {
     let mut open_stock_dates = Vec::<(i32, u32, u32, i32)>::new();
 
     for month in 1..=12 {
-        let str_ym = format!("{0:04}{1:02}", year, month);
-
-        let price_data;
-        if last_price_date.starts_with(&str_ym) {
+        let price_data = if (last_price_date.year(), last_price_date.month()) == (year, month) {
             // 收盤價清單
-            price_data = security_price::dao::find_all_by_date(
-                &format!("{0:04}", year),
-                &format!("{0:02}", month),
-                "",
-            )
-            .await;
+            let first_day = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+            let last_day = last_day_in_month(year, month);
+            security_price::dao::find_all_by_date(repo, first_day, last_day).await
         } else {
-            price_data = Vec::<SecurityPrice>::new();
-        }
+            Vec::<SecurityPrice>::new()
+        };
 
         open_stock_dates.append(&mut get_open_stock_date(
             year,
@@ -65,34 +80,33 @@ async fn get_open_stock_month(year: i32, last_price_date: &str) -> Vec<(i32, u32
 fn get_open_stock_date(
     year: i32,
     month: u32,
-    last_price_date: &str,
-    price_data: &Vec<SecurityPrice>,
+    last_price_date: NaiveDate,
+    price_data: &[SecurityPrice],
 ) -> Vec<(i32, u32, u32, i32)> {
     let mut open_stock_dates = Vec::<(i32, u32, u32, i32)>::new();
 
     let mut open_stock_index = 0;
     let last_day = last_day_in_month(year, month).day();
     for day in 1..=last_day {
-        if last_price_date.to_string() >= format!("{0:04}{1:02}{2:02}", year, month, day) {
+        let date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        if last_price_date >= date {
             let security_codes: Vec<String> = price_data
                 .iter()
-                .filter(|x| {
-                    x.price_date == format!("{0:04}/{1:02}/{2:02}", year - 1911, month, day)
-                })
+                .filter(|x| x.trade_date == Some(date))
                 .map(|x| x.security_code.clone())
                 .collect();
 
-            if security_codes.len() > 0 {
-                open_stock_dates.push((year, month, day, open_stock_index));
-                open_stock_index = open_stock_index + 1;
-            } else {
+            if security_codes.is_empty() {
                 open_stock_dates.push((year, month, day, -1));
+            } else {
+                open_stock_dates.push((year, month, day, open_stock_index));
+                open_stock_index += 1;
             }
         } else {
             let weekday = get_weekday(year, month, day);
             if weekday < 6 {
                 open_stock_dates.push((year, month, day, open_stock_index));
-                open_stock_index = open_stock_index + 1;
+                open_stock_index += 1;
             } else {
                 open_stock_dates.push((year, month, day, -1));
             }
@@ -113,105 +127,81 @@ fn get_new_calendar_date(
     task: &str,
 ) -> CalendarData {
     CalendarData {
-        row_id: "".to_string(), // or any appropriate value
-        ce_year: format!("{0:04}", year),
-        ce_month: format!("{0:02}", month),
-        ce_day: format!("{0:02}", day),
+        row_id: String::new(), // or any appropriate value
+        ce_year: format!("{year:04}"),
+        ce_month: format!("{month:02}"),
+        ce_day: format!("{day:02}"),
+        ce_date: NaiveDate::from_ymd_opt(year, month, day).unwrap(),
         week_index: get_weekday(year, month, day),
         date_status: status.to_string(),
         group_task: task.to_string(),
     }
 }
 
-async fn check_data_exists(data: &CalendarData) -> bool {
-    dao::find_one(
-        &data.ce_year,
-        &data.ce_month,
-        &data.ce_day,
-    )
-    .await
-    .is_some()
+///
+/// 取得任務群組 (point: YYYYMMDD)
+///
+fn get_group_task(point: &str, date_status: &str, open_index: i32) -> &'static str {
+    // 初始化任務截止日
+    let init_point = config::service::get()
+        .calendar
+        .init_point
+        .format("%Y%m%d")
+        .to_string();
+
+    match (date_status, init_point.as_str() > point, open_index) {
+        ("S", _, _) => "STOP",
+        (_, true, 0) => "FIRST_INIT",
+        (_, true, _) => "INIT",
+        (_, false, 0) => "FIRST",
+        (_, false, _) => "SECURITY",
+    }
+}
+
+async fn check_data_exists(repo: &Repository, data: &CalendarData) -> bool {
+    dao::find_one(repo, &data.ce_year, &data.ce_month, &data.ce_day)
+        .await
+        .is_some()
 }
 
-pub async fn init_calendar_data() -> Result<(), sqlx::Error> {
+pub async fn init_calendar_data(repo: &Repository) -> Result<(), Error> {
     let max_year = Local::now().year();
-    let min_year = 1999;
+    let min_year = config::service::get().calendar.min_year;
 
     let mut calendar_datas = Vec::<CalendarData>::new();
 
-    let max_price_date = security_price::dao::find_one_by_maxdate().await;
-    let start_point = "20250101".to_string();
+    let max_price_date = security_price::dao::find_one_by_maxdate(repo).await;
 
     for y in min_year..=max_year {
-        let open_stock_dates = get_open_stock_month(y, &max_price_date).await;
+        let open_stock_dates = get_open_stock_month(repo, y, max_price_date).await;
         for open_stock_date in open_stock_dates {
             let point = format!(
                 "{0:04}{1:02}{2:02}",
                 open_stock_date.0, open_stock_date.1, open_stock_date.2
             );
 
-            if open_stock_date.3 == -1 {
-                calendar_datas.push(get_new_calendar_date(
-                    open_stock_date.0,
-                    open_stock_date.1,
-                    open_stock_date.2,
-                    "S",
-                    "STOP",
-                ));
-            }
-
-            if start_point > point {
-                if open_stock_date.3 == 0 {
-                    calendar_datas.push(get_new_calendar_date(
-                        open_stock_date.0,
-                        open_stock_date.1,
-                        open_stock_date.2,
-                        "O",
-                        "FIRST_INIT",
-                    ));
-                }
-                if open_stock_date.3 > 0 {
-                    calendar_datas.push(get_new_calendar_date(
-                        open_stock_date.0,
-                        open_stock_date.1,
-                        open_stock_date.2,
-                        "O",
-                        "INIT",
-                    ));
-                }
-            } else {
-                if open_stock_date.3 == 0 {
-                    calendar_datas.push(get_new_calendar_date(
-                        open_stock_date.0,
-                        open_stock_date.1,
-                        open_stock_date.2,
-                        "O",
-                        "FIRST",
-                    ));
-                }
-                if open_stock_date.3 > 0 {
-                    calendar_datas.push(get_new_calendar_date(
-                        open_stock_date.0,
-                        open_stock_date.1,
-                        open_stock_date.2,
-                        "O",
-                        "SECURITY",
-                    ));
-                }
-            }
+            let date_status = if open_stock_date.3 == -1 { "S" } else { "O" };
+            calendar_datas.push(get_new_calendar_date(
+                open_stock_date.0,
+                open_stock_date.1,
+                open_stock_date.2,
+                date_status,
+                get_group_task(&point, date_status, open_stock_date.3),
+            ));
         }
     }
 
     for calendar_data in calendar_datas {
-        if !check_data_exists(&calendar_data).await {
-            dao::create(calendar_data).await?;
+        if !check_data_exists(repo, &calendar_data).await {
+            dao::create(repo, calendar_data).await?;
         }
     }
 
     Ok(())
 }
 
-pub async fn insert_calendar_data(open_next_year: bool) -> Result<(), sqlx::Error> {
+/// 新增年度行事曆 (優先使用證交所公告的開休市日期，無法取得時依星期判斷)
+pub async fn insert_calendar_data(repo: &Repository, open_next_year: bool) -> Result<(), Error> {
     let now = Local::now().date_naive();
     let year = if open_next_year {
         now.year() + 1
@@ -219,44 +209,172 @@ pub async fn insert_calendar_data(open_next_year: bool) -> Result<(), sqlx::Erro
         now.year()
     };
 
+    match get_holiday_schedule(year)
+        .await
+        .and_then(|content| parse_holiday_schedule(&content))
+    {
+        Ok(holidays) => return import_holiday_data(repo, year, &holidays).await,
+        Err(e) => {
+            event!(target: "security_api", Level::WARN, "calendar_data.holiday_schedule {} {}", year, &e);
+        }
+    }
+
     let mut calendar_datas = Vec::<CalendarData>::new();
 
-    let max_price_date = security_price::dao::find_one_by_maxdate().await;
+    let max_price_date = security_price::dao::find_one_by_maxdate(repo).await;
 
-    let open_stock_dates = get_open_stock_month(year, &max_price_date).await;
+    let open_stock_dates = get_open_stock_month(repo, year, max_price_date).await;
     for open_stock_date in open_stock_dates {
-        if open_stock_date.3 == -1 {
-            calendar_datas.push(get_new_calendar_date(
-                open_stock_date.0,
-                open_stock_date.1,
-                open_stock_date.2,
-                "S",
-                "STOP",
-            ));
+        let point = format!(
+            "{0:04}{1:02}{2:02}",
+            open_stock_date.0, open_stock_date.1, open_stock_date.2
+        );
+
+        let date_status = if open_stock_date.3 == -1 { "S" } else { "O" };
+        calendar_datas.push(get_new_calendar_date(
+            open_stock_date.0,
+            open_stock_date.1,
+            open_stock_date.2,
+            date_status,
+            get_group_task(&point, date_status, open_stock_date.3),
+        ));
+    }
+
+    for calendar_data in calendar_datas {
+        if !check_data_exists(repo, &calendar_data).await {
+            dao::create(repo, calendar_data).await?;
         }
-        if open_stock_date.3 == 0 {
-            calendar_datas.push(get_new_calendar_date(
-                open_stock_date.0,
-                open_stock_date.1,
-                open_stock_date.2,
-                "O",
-                "FRIST",
-            ));
+    }
+
+    Ok(())
+}
+
+/// 下載市場開休市日期
+pub async fn get_holiday_schedule(year: i32) -> Result<String, Error> {
+    let first_day = NaiveDate::from_ymd_opt(year, 1, 1)
+        .ok_or_else(|| Error::Parse(format!("holiday year {year}")))?;
+    let roc_year = RocDate::from(first_day).year();
+
+    let holiday = &config::service::get().holiday;
+    let res = Retry::start(holiday.retry.strategy(), || {
+        market_source::service::client()
+            .get(&holiday.url)
+            .query(&[("response", "json"), ("queryYear", &roc_year.to_string())])
+            .timeout(holiday.timeout())
+            .send()
+    })
+    .await?;
+    event!(target: "security_api", Level::DEBUG, "{:?}", &res.url().to_string());
+
+    let res = response_data::service::check_status(res)?;
+    Ok(res.text().await?)
+}
+
+/// 解析市場開休市日期
+///
+/// # Errors
+/// 格式錯誤或查詢失敗時
+pub fn parse_holiday_schedule(content: &str) -> Result<Vec<HolidayDate>, Error> {
+    let schedule = serde_json::from_str::<HolidaySchedule>(content)?;
+    if !schedule.stat.eq_ignore_ascii_case("ok") {
+        return Err(Error::UpstreamRejected(format!(
+            "holiday schedule {}",
+            schedule.stat
+        )));
+    }
+
+    let fields = schedule.fields.unwrap_or_default();
+    let name_index = get_field_index(&fields, "名稱").unwrap_or(0);
+    let date_index = get_field_index(&fields, "日期").unwrap_or(1);
+    let description_index = get_field_index(&fields, "說明").unwrap_or(3);
+
+    schedule
+        .data
+        .unwrap_or_default()
+        .iter()
+        .map(|row| {
+            let value = |index: usize| row.get(index).map_or("", |x| x.trim());
+
+            let name = value(name_index).to_string();
+            let description = value(description_index).to_string();
+            Ok(HolidayDate {
+                ce_date: parse_holiday_date(value(date_index))?,
+                date_status: get_holiday_status(&name, &description).to_string(),
+                name,
+                description,
+            })
+        })
+        .collect()
+}
+
+/// 取得欄位位置
+fn get_field_index(fields: &[String], name: &str) -> Option<usize> {
+    fields.iter().position(|x| x.trim() == name)
+}
+
+/// 解析日期 (2024-01-01 或民國日期)
+fn parse_holiday_date(value: &str) -> Result<NaiveDate, Error> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| RocDate::from_str(value).map(RocDate::to_naive))
+}
+
+/// 依名稱與說明判斷開休市
+fn get_holiday_status(name: &str, description: &str) -> &'static str {
+    if OPEN_KEYWORDS
+        .iter()
+        .any(|x| name.contains(x) || description.contains(x))
+    {
+        "O"
+    } else {
+        "S"
+    }
+}
+
+/// 依市場開休市日期更新年度行事曆
+pub async fn import_holiday_data(
+    repo: &Repository,
+    year: i32,
+    holidays: &[HolidayDate],
+) -> Result<(), Error> {
+    let first_day = NaiveDate::from_ymd_opt(year, 1, 1)
+        .ok_or_else(|| Error::Parse(format!("holiday year {year}")))?;
+
+    let mut open_index = 0;
+    for date in first_day.iter_days().take_while(|x| x.year() == year) {
+        if date.day() == 1 {
+            open_index = 0;
         }
-        if open_stock_date.3 > 0 {
-            calendar_datas.push(get_new_calendar_date(
-                open_stock_date.0,
-                open_stock_date.1,
-                open_stock_date.2,
-                "O",
-                "SECURITY",
-            ));
+
+        let date_status = match holidays.iter().find(|x| x.ce_date == date) {
+            Some(holiday) => holiday.date_status.as_str(),
+            None if date.weekday().number_from_monday() < 6 => "O",
+            None => "S",
+        };
+        let point = date.format("%Y%m%d").to_string();
+        let group_task = get_group_task(&point, date_status, open_index);
+        if date_status == "O" {
+            open_index += 1;
         }
-    }
 
-    for calendar_data in calendar_datas {
-        if !check_data_exists(&calendar_data).await {
-            dao::create(calendar_data).await?;
+        let mut calendar_data =
+            get_new_calendar_date(year, date.month(), date.day(), date_status, group_task);
+        match dao::find_one(
+            repo,
+            &calendar_data.ce_year,
+            &calendar_data.ce_month,
+            &calendar_data.ce_day,
+        )
+        .await
+        {
+            Some(data) if data.date_status == date_status && data.group_task == group_task => {}
+            Some(data) => {
+                event!(target: "security_api", Level::INFO, "holiday {} {} -> {}", &date, &data.date_status, date_status);
+                calendar_data.row_id = data.row_id;
+                dao::modify(repo, calendar_data).await?;
+            }
+            None => {
+                dao::create(repo, calendar_data).await?;
+            }
         }
     }
 
@@ -0,0 +1,260 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::{iter::Take, time::Duration};
+
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use tokio_retry::strategy::ExponentialBackoff;
+
+/// 批次設定 (`security_api.toml`，可由 `SECURITY_API_*` 環境變數覆寫)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// 市場交易時間
+    pub market: MarketConfig,
+    /// 上市 (證交所)
+    pub twse: SourceConfig,
+    /// 上櫃、興櫃 (櫃買中心)
+    pub tpex: SourceConfig,
+    /// 證券代碼來源
+    pub isin: EndpointConfig,
+    /// 市場開休市日期來源
+    pub holiday: EndpointConfig,
+    /// 行事曆初始化
+    pub calendar: CalendarConfig,
+    /// 證券任務
+    pub task: TaskConfig,
+    /// 流程租約
+    pub flow: FlowConfig,
+    /// 資料庫連線池
+    pub database: DatabaseConfig,
+    /// 資料庫備份
+    pub backup: BackupConfig,
+}
+
+/// 市場交易時間
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarketConfig {
+    /// 當日資料可查詢時間 (HH:MM:SS)
+    pub close_time: NaiveTime,
+}
+
+/// 市場資料來源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SourceConfig {
+    /// 來源主機 (不含結尾 `/`)
+    pub host: String,
+    /// 查詢逾時 (秒)
+    pub timeout_secs: u64,
+    /// 每個額度的補充間隔 (秒)
+    pub interval_secs: u64,
+    /// 最大累積額度
+    pub burst: u32,
+    /// 同時查詢數
+    pub concurrency: usize,
+    /// 被限制流量時的最短暫停 (秒)
+    pub backoff_min_secs: u64,
+    /// 被限制流量時的最長暫停 (秒)
+    pub backoff_max_secs: u64,
+}
+
+/// 單一網址來源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EndpointConfig {
+    /// 查詢網址
+    pub url: String,
+    /// 查詢逾時 (秒)
+    pub timeout_secs: u64,
+    /// 查詢失敗時的重試設定
+    pub retry: RetryConfig,
+}
+
+/// 重試設定 (指數退避)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetryConfig {
+    /// 第一次重試前的等待 (毫秒)
+    pub base_millis: u64,
+    /// 每次重試的最長等待 (秒)
+    pub max_delay_secs: u64,
+    /// 重試次數
+    pub count: usize,
+}
+
+/// 行事曆初始化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CalendarConfig {
+    /// 初始化起始年度
+    pub min_year: i32,
+    /// 初始化任務截止日 (之前的日期列為 INIT 群組)
+    pub init_point: NaiveDate,
+}
+
+/// 證券任務
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskConfig {
+    /// 每個任務的重試上限 (超過標記為失敗)
+    pub max_retry_count: i32,
+    /// 每個任務查無資料時的執行次數上限 (超過不再認領)
+    pub max_exec_count: i32,
+    /// 每個任務連續被限制流量的上限 (超過標記為失敗)
+    pub max_throttle_count: u32,
+    /// 認領逾時秒數 (處理中每三分之一更新一次，超過未更新視為程序中斷，由其他程序回收)
+    pub claim_timeout_secs: i32,
+    /// 單次查詢的重試設定
+    pub retry: RetryConfig,
+}
+
+/// 流程租約
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlowConfig {
+    /// 租約秒數 (超過未更新視為中斷，其他程序可接手)
+    pub lease_secs: i32,
+    /// 心跳間隔 (秒)
+    pub heartbeat_secs: u64,
+}
+
+/// 資料庫連線池
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// 最大連線數
+    pub max_connections: u32,
+    /// 最小連線數
+    pub min_connections: u32,
+    /// 取得連線逾時 (秒)
+    pub acquire_timeout_secs: u64,
+    /// 閒置連線逾時 (秒)
+    pub idle_timeout_secs: u64,
+    /// 預備語句快取數
+    pub statement_cache: usize,
+}
+
+/// 資料庫備份
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackupConfig {
+    /// 備份的資料庫名稱
+    pub database: String,
+    /// 不備份的資料表
+    pub exclude_tables: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            market: MarketConfig {
+                close_time: NaiveTime::from_hms_opt(15, 30, 0).unwrap_or_default(),
+            },
+            twse: SourceConfig {
+                host: "https://www.twse.com.tw".to_string(),
+                timeout_secs: 4,
+                interval_secs: 3,
+                burst: 2,
+                concurrency: 2,
+                backoff_min_secs: 30,
+                backoff_max_secs: 600,
+            },
+            tpex: SourceConfig {
+                host: "https://www.tpex.org.tw".to_string(),
+                timeout_secs: 4,
+                interval_secs: 2,
+                burst: 2,
+                concurrency: 2,
+                backoff_min_secs: 30,
+                backoff_max_secs: 600,
+            },
+            isin: EndpointConfig {
+                url: "https://isin.twse.com.tw/isin/class_main.jsp".to_string(),
+                timeout_secs: 20,
+                retry: RetryConfig {
+                    base_millis: 2000,
+                    max_delay_secs: 10,
+                    count: 5,
+                },
+            },
+            holiday: EndpointConfig {
+                url: "https://www.twse.com.tw/rwd/zh/holidaySchedule/holidaySchedule".to_string(),
+                timeout_secs: 10,
+                retry: RetryConfig {
+                    base_millis: 2000,
+                    max_delay_secs: 10,
+                    count: 0,
+                },
+            },
+            calendar: CalendarConfig {
+                min_year: 1999,
+                init_point: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap_or_default(),
+            },
+            task: TaskConfig {
+                max_retry_count: 3,
+                max_exec_count: 3,
+                max_throttle_count: 5,
+                claim_timeout_secs: 1800,
+                retry: RetryConfig {
+                    base_millis: 2000,
+                    max_delay_secs: 2,
+                    count: 5,
+                },
+            },
+            flow: FlowConfig {
+                lease_secs: 300,
+                heartbeat_secs: 60,
+            },
+            database: DatabaseConfig {
+                max_connections: 10,
+                min_connections: 0,
+                acquire_timeout_secs: 30,
+                idle_timeout_secs: 600,
+                statement_cache: 100,
+            },
+            backup: BackupConfig {
+                database: "security_api".to_string(),
+                exclude_tables: vec![
+                    "_sqlx_migrations".to_string(),
+                    "listen_flow".to_string(),
+                    "security_temp".to_string(),
+                ],
+            },
+        }
+    }
+}
+
+impl SourceConfig {
+    /// 查詢逾時
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+impl EndpointConfig {
+    /// 查詢逾時
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+impl RetryConfig {
+    /// 重試等待間隔
+    pub fn strategy(&self) -> Take<ExponentialBackoff> {
+        ExponentialBackoff::from_millis(self.base_millis)
+            .max_delay(Duration::from_secs(self.max_delay_secs))
+            .take(self.count)
+    }
+}
+
+impl FlowConfig {
+    /// 心跳間隔
+    #[must_use]
+    pub fn heartbeat(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_secs)
+    }
+}
@@ -0,0 +1,240 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use chrono::{Datelike, Local};
+use dotenvy::dotenv;
+use reqwest::Url;
+use toml::{Table, Value};
+
+use super::model::{Config, EndpointConfig, SourceConfig};
+use crate::Error;
+
+/// 預設設定檔 (不存在時使用預設值)
+const CONFIG_FILE: &str = "security_api.toml";
+/// 指定設定檔的環境變數
+const CONFIG_ENV: &str = "SECURITY_API_CONFIG";
+/// 覆寫設定的環境變數前綴 (`SECURITY_API_TWSE_HOST` 對應 `[twse] host`)
+const ENV_PREFIX: &str = "SECURITY_API";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// 目前設定 (公開入口先以 `try_get` 檢查)
+///
+/// # Panics
+/// 尚未以 `init` 載入設定時
+pub fn get() -> &'static Config {
+    try_get().expect("config not loaded, call init_config first")
+}
+
+/// 目前設定
+///
+/// # Errors
+/// 尚未以 `init` 載入設定時
+pub fn try_get() -> Result<&'static Config, Error> {
+    CONFIG
+        .get()
+        .ok_or_else(|| Error::Config("config not loaded, call init_config first".to_string()))
+}
+
+/// 載入並驗證設定 (程式啟動時呼叫一次)
+///
+/// # Errors
+/// 設定檔無法讀取、格式錯誤、驗證失敗或已載入過時
+pub fn init(path: Option<&Path>) -> Result<&'static Config, Error> {
+    let config = load(path)?;
+    CONFIG
+        .set(config)
+        .map_err(|_| Error::Config("config already loaded".to_string()))?;
+    Ok(get())
+}
+
+/// 讀取設定 (預設值 < 設定檔 < 環境變數)
+///
+/// # Errors
+/// 設定檔無法讀取、格式錯誤或驗證失敗時
+pub fn load(path: Option<&Path>) -> Result<Config, Error> {
+    dotenv().ok();
+
+    let mut table = Table::try_from(Config::default()).map_err(|e| Error::Config(e.to_string()))?;
+
+    let env_path = env::var_os(CONFIG_ENV).map(PathBuf::from);
+    match path.map(Path::to_path_buf).or(env_path) {
+        Some(path) => merge_table(&mut table, read_file(&path)?),
+        None if Path::new(CONFIG_FILE).exists() => {
+            merge_table(&mut table, read_file(Path::new(CONFIG_FILE))?);
+        }
+        None => {}
+    }
+
+    let vars = env::vars().collect::<HashMap<String, String>>();
+    apply_env(&mut table, ENV_PREFIX, &vars)?;
+
+    let config = table
+        .try_into::<Config>()
+        .map_err(|e| Error::Config(e.to_string()))?;
+    validate(&config)?;
+
+    Ok(config)
+}
+
+/// 讀取設定檔
+fn read_file(path: &Path) -> Result<Table, Error> {
+    let content =
+        fs::read_to_string(path).map_err(|e| Error::Config(format!("{}: {e}", path.display())))?;
+    content
+        .parse::<Table>()
+        .map_err(|e| Error::Config(format!("{}: {e}", path.display())))
+}
+
+/// 以設定檔覆寫預設值 (子表逐項合併)
+fn merge_table(base: &mut Table, other: Table) {
+    for (key, value) in other {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(other_table)) => {
+                merge_table(base_table, other_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// 以環境變數覆寫設定 (字串直接使用，其餘依 TOML 格式解析)
+fn apply_env(table: &mut Table, prefix: &str, vars: &HashMap<String, String>) -> Result<(), Error> {
+    for (key, value) in table.iter_mut() {
+        let name = format!("{prefix}_{key}").to_uppercase();
+
+        if let Value::Table(sub_table) = value {
+            apply_env(sub_table, &name, vars)?;
+            continue;
+        }
+
+        let Some(raw) = vars.get(&name) else {
+            continue;
+        };
+        *value = match value {
+            Value::String(_) => Value::String(raw.clone()),
+            _ => format!("value = {raw}")
+                .parse::<Table>()
+                .ok()
+                .and_then(|mut x| x.remove("value"))
+                .ok_or_else(|| Error::Config(format!("{name}: invalid value `{raw}`")))?,
+        };
+    }
+    Ok(())
+}
+
+/// 驗證設定
+fn validate(config: &Config) -> Result<(), Error> {
+    let mut errors = Vec::<String>::new();
+
+    validate_source("twse", &config.twse, &mut errors);
+    validate_source("tpex", &config.tpex, &mut errors);
+    validate_endpoint("isin", &config.isin, &mut errors);
+    validate_endpoint("holiday", &config.holiday, &mut errors);
+
+    let calendar = &config.calendar;
+    if calendar.min_year < 1900 || calendar.min_year > Local::now().year() {
+        errors.push(format!(
+            "calendar.min_year {} out of range",
+            calendar.min_year
+        ));
+    }
+
+    if config.task.max_retry_count < 1 {
+        errors.push("task.max_retry_count must be at least 1".to_string());
+    }
+    if config.task.max_exec_count < 1 {
+        errors.push("task.max_exec_count must be at least 1".to_string());
+    }
+    if config.task.max_throttle_count < 1 {
+        errors.push("task.max_throttle_count must be at least 1".to_string());
+    }
+    if config.task.claim_timeout_secs < 1 {
+        errors.push("task.claim_timeout_secs must be positive".to_string());
+    }
+
+    let database = &config.database;
+    if database.max_connections == 0 || database.min_connections > database.max_connections {
+        errors.push(
+            "database.max_connections must be positive and not less than database.min_connections"
+                .to_string(),
+        );
+    }
+    if database.acquire_timeout_secs == 0 {
+        errors.push("database.acquire_timeout_secs must be positive".to_string());
+    }
+
+    let flow = &config.flow;
+    if flow.heartbeat_secs == 0
+        || flow.heartbeat_secs >= u64::try_from(flow.lease_secs).unwrap_or(0)
+    {
+        errors
+            .push("flow.heartbeat_secs must be positive and less than flow.lease_secs".to_string());
+    }
+
+    if config.backup.database.trim().is_empty() {
+        errors.push("backup.database must not be empty".to_string());
+    }
+    if config
+        .backup
+        .exclude_tables
+        .iter()
+        .any(|x| x.trim().is_empty())
+    {
+        errors.push("backup.exclude_tables must not contain empty names".to_string());
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Config(errors.join("; ")))
+    }
+}
+
+/// 驗證市場資料來源
+fn validate_source(name: &str, source: &SourceConfig, errors: &mut Vec<String>) {
+    validate_url(&format!("{name}.host"), &source.host, errors);
+    if source.host.ends_with('/') {
+        errors.push(format!("{name}.host must not end with `/`"));
+    }
+    if source.timeout_secs == 0 || source.interval_secs == 0 {
+        errors.push(format!(
+            "{name}.timeout_secs and {name}.interval_secs must be positive"
+        ));
+    }
+    if source.burst == 0 || source.concurrency == 0 {
+        errors.push(format!(
+            "{name}.burst and {name}.concurrency must be at least 1"
+        ));
+    }
+    if source.backoff_min_secs > source.backoff_max_secs {
+        errors.push(format!(
+            "{name}.backoff_min_secs exceeds {name}.backoff_max_secs"
+        ));
+    }
+}
+
+/// 驗證單一網址來源
+fn validate_endpoint(name: &str, endpoint: &EndpointConfig, errors: &mut Vec<String>) {
+    validate_url(&format!("{name}.url"), &endpoint.url, errors);
+    if endpoint.timeout_secs == 0 {
+        errors.push(format!("{name}.timeout_secs must be positive"));
+    }
+}
+
+/// 驗證網址 (僅接受 http/https)
+fn validate_url(name: &str, value: &str, errors: &mut Vec<String>) {
+    match Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        Ok(_) => errors.push(format!("{name} `{value}` must be http or https")),
+        Err(e) => errors.push(format!("{name} `{value}` {e}")),
+    }
+}
@@ -1,5 +1,5 @@
 #![warn(clippy::all, clippy::pedantic)]
-use chrono::{Datelike, Local};
+use chrono::{Local, NaiveDate};
 use sqlx::{postgres::PgRow, Row};
 use tracing::{event, Level};
 
@@ -7,9 +7,8 @@ use crate::repository::Repository;
 
 use super::model::DailyTask;
 
-pub async fn create(data: DailyTask) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn create(repo: &Repository, data: DailyTask) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -17,21 +16,23 @@ pub async fn create(data: DailyTask) -> Result<u64, sqlx::Error> {
             open_date_year
           , open_date_month
           , open_date_day
+          , open_date
           , job_code
           , exec_status
           , created_date
           , updated_date
-        ) VALUES ( $1, $2, $3, $4, $5, $6, $7 )
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8 )
     ",
     )
     .bind(data.open_date_year)
     .bind(data.open_date_month)
     .bind(data.open_date_day)
+    .bind(data.open_date)
     .bind(data.job_code)
     .bind(data.exec_status)
     .bind(Local::now())
     .bind(Local::now())
-    .execute(&conn)
+    .execute(conn)
     .await
     {
         Ok(cnt) => Ok(cnt.rows_affected()),
@@ -39,28 +40,29 @@ pub async fn create(data: DailyTask) -> Result<u64, sqlx::Error> {
     }
 }
 
-pub async fn modify(data: DailyTask) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn modify(repo: &Repository, data: DailyTask) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
         UPDATE daily_task 
            SET exec_status = $1
-             , updated_date = $2
-         WHERE open_date_year = $3
-           AND open_date_month = $4
-           AND open_date_day = $5
-           AND job_code = $6
+             , last_error = $2
+             , updated_date = $3
+         WHERE open_date_year = $4
+           AND open_date_month = $5
+           AND open_date_day = $6
+           AND job_code = $7
     ",
     )
     .bind(data.exec_status)
+    .bind(data.last_error)
     .bind(Local::now())
     .bind(data.open_date_year)
     .bind(data.open_date_month)
     .bind(data.open_date_day)
     .bind(data.job_code)
-    .execute(&conn)
+    .execute(conn)
     .await
     {
         Ok(cnt) => Ok(cnt.rows_affected()),
@@ -68,11 +70,8 @@ pub async fn modify(data: DailyTask) -> Result<u64, sqlx::Error> {
     }
 }
 
-pub async fn find_all() -> Vec<DailyTask> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
-
-    let now = Local::now();
+pub async fn find_all(repo: &Repository, open_date: NaiveDate) -> Vec<DailyTask> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -80,8 +79,10 @@ pub async fn find_all() -> Vec<DailyTask> {
              , cd.ce_year AS open_date_year
              , cd.ce_month AS open_date_month
              , cd.ce_day AS open_date_day
+             , cd.ce_date AS open_date
              , ts.job_code 
              , 'WAIT' AS exec_status
+             , '' AS last_error
           FROM calendar_data cd
           JOIN task_setting ts
             ON cd.group_task = ts.group_code
@@ -89,30 +90,25 @@ pub async fn find_all() -> Vec<DailyTask> {
          WHERE NOT EXISTS (
                SELECT 1 
                  FROM daily_task dt
-                WHERE dt.open_date_year = cd.ce_year 
-                  AND dt.open_date_month = cd.ce_month
-                  AND dt.open_date_day = cd.ce_day
+                WHERE dt.open_date = cd.ce_date
                   AND dt.job_code = ts.job_code
          )
-           AND concat(cd.ce_year,cd.ce_month,cd.ce_day) = $1
+           AND cd.ce_date = $1
            AND cd.date_status = 'O'
          ORDER BY cd.ce_year desc, cd.ce_month desc, cd.ce_day desc, ts.sort_no ",
     )
-    .bind(format!(
-        "{0:04}{1:02}{2:02}",
-        now.year(),
-        now.month(),
-        now.day()
-    ))
+    .bind(open_date)
     .map(|row: PgRow| DailyTask {
         row_id: row.get("row_id"),
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         job_code: row.get("job_code"),
         exec_status: row.get("exec_status"),
+        last_error: row.get("last_error"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
         Ok(rows) => rows,
@@ -124,13 +120,13 @@ pub async fn find_all() -> Vec<DailyTask> {
 }
 
 pub async fn find_one(
+    repo: &Repository,
     q_year: &str,
     q_month: &str,
     q_day: &str,
     q_job_code: &str,
 ) -> Option<DailyTask> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -138,8 +134,10 @@ pub async fn find_one(
              , open_date_year
              , open_date_month
              , open_date_day
+             , open_date
              , job_code
              , exec_status
+             , last_error
           FROM daily_task
          WHERE open_date_year = $1
            AND open_date_month = $2
@@ -155,11 +153,13 @@ pub async fn find_one(
         row_id: row.get("row_id"),
         job_code: row.get("job_code"),
         exec_status: row.get("exec_status"),
-        open_date_year: row.get("exec_status"),
-        open_date_month: row.get("exec_status"),
-        open_date_day: row.get("exec_status"),
+        last_error: row.get("last_error"),
+        open_date_year: row.get("open_date_year"),
+        open_date_month: row.get("open_date_month"),
+        open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
     })
-    .fetch_optional(&conn)
+    .fetch_optional(conn)
     .await
     {
         Ok(row) => row,
@@ -170,43 +170,56 @@ pub async fn find_one(
     }
 }
 
-pub async fn find_one_by_exec_asc(flow_code: &str) -> Option<DailyTask> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_one_by_exec_asc(
+    repo: &Repository,
+    flow_code: &str,
+    q_start_date: Option<NaiveDate>,
+    q_end_date: Option<NaiveDate>,
+) -> Option<DailyTask> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
         SELECT distinct '' AS row_id
                  , '' AS job_code 
                  , '' AS exec_status
+                 , '' AS last_error
                  , now() AS created_date
                  , now() AS updated_date 
                  , dt.open_date_year
                  , dt.open_date_month
                  , dt.open_date_day
+                 , dt.open_date
           FROM daily_task dt
-         WHERE dt.exec_status in ('WAIT', 'OPEN', 'EXEC')
+         WHERE dt.exec_status in ('WAIT', 'OPEN', 'EXEC', 'FAIL')
            AND NOT EXISTS (
                SELECT 1 
                  FROM listen_flow lf
                 WHERE lf.flow_code = $1
                   AND lf.flow_param1 = dt.open_date_year
                   AND lf.flow_param2 = dt.open_date_month
+                  AND (lf.pstatus = 'EXIT' OR lf.lease_until >= now())
             )
-         ORDER BY dt.open_date_year, dt.open_date_month, dt.open_date_day
+           AND ($2::date IS NULL OR dt.open_date >= $2)
+           AND ($3::date IS NULL OR dt.open_date < $3)
+         ORDER BY dt.open_date
          Limit 1
     ",
     )
     .bind(flow_code)
+    .bind(q_start_date)
+    .bind(q_end_date)
     .map(|row: PgRow| DailyTask {
         row_id: row.get("row_id"),
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         job_code: row.get("job_code"),
         exec_status: row.get("exec_status"),
+        last_error: row.get("last_error"),
     })
-    .fetch_optional(&conn)
+    .fetch_optional(conn)
     .await
     {
         Ok(row) => row,
@@ -217,43 +230,56 @@ pub async fn find_one_by_exec_asc(flow_code: &str) -> Option<DailyTask> {
     }
 }
 
-pub async fn find_one_by_exec_desc(flow_code: &str) -> Option<DailyTask> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_one_by_exec_desc(
+    repo: &Repository,
+    flow_code: &str,
+    q_start_date: Option<NaiveDate>,
+    q_end_date: Option<NaiveDate>,
+) -> Option<DailyTask> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
         SELECT distinct '' AS row_id
                  , '' AS job_code 
                  , '' AS exec_status
+                 , '' AS last_error
                  , now() AS created_date
                  , now() AS updated_date 
                  , dt.open_date_year
                  , dt.open_date_month
                  , dt.open_date_day
+                 , dt.open_date
           FROM daily_task dt
-         WHERE dt.exec_status in ('WAIT', 'OPEN', 'EXEC')
+         WHERE dt.exec_status in ('WAIT', 'OPEN', 'EXEC', 'FAIL')
            AND NOT EXISTS (
                SELECT 1 
                  FROM listen_flow lf
                 WHERE lf.flow_code = $1
                   AND lf.flow_param1 = dt.open_date_year
                   AND lf.flow_param2 = dt.open_date_month
+                  AND (lf.pstatus = 'EXIT' OR lf.lease_until >= now())
             )
-         ORDER BY dt.open_date_year desc, dt.open_date_month desc, dt.open_date_day desc
+           AND ($2::date IS NULL OR dt.open_date >= $2)
+           AND ($3::date IS NULL OR dt.open_date < $3)
+         ORDER BY dt.open_date desc
          Limit 1
     ",
     )
     .bind(flow_code)
+    .bind(q_start_date)
+    .bind(q_end_date)
     .map(|row: PgRow| DailyTask {
         row_id: row.get("row_id"),
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         job_code: row.get("job_code"),
         exec_status: row.get("exec_status"),
+        last_error: row.get("last_error"),
     })
-    .fetch_optional(&conn)
+    .fetch_optional(conn)
     .await
     {
         Ok(row) => row,
@@ -264,9 +290,12 @@ pub async fn find_one_by_exec_desc(flow_code: &str) -> Option<DailyTask> {
     }
 }
 
-pub async fn find_all_by_exec_asc(q_year: &str, q_month: &str) -> Vec<DailyTask> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_all_by_exec_asc(
+    repo: &Repository,
+    q_start_date: NaiveDate,
+    q_end_date: NaiveDate,
+) -> Vec<DailyTask> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -274,34 +303,36 @@ pub async fn find_all_by_exec_asc(q_year: &str, q_month: &str) -> Vec<DailyTask>
              , dt.open_date_year
              , dt.open_date_month
              , dt.open_date_day
+             , dt.open_date
              , dt.job_code
              , dt.exec_status
+             , dt.last_error
           FROM daily_task dt
           JOIN calendar_data cd
-            ON dt.open_date_year = cd.ce_year
-           AND dt.open_date_month = cd.ce_month
-           AND dt.open_date_day = cd.ce_day
+            ON cd.ce_date = dt.open_date
           JOIN task_setting ts
             ON ts.group_code = cd.group_task 
            AND ts.job_code = dt.job_code
            AND ts.is_enabled = 1
-         WHERE dt.open_date_year = $1
-           AND dt.open_date_month = $2
-           AND dt.exec_status in ('WAIT', 'OPEN', 'EXEC')
-         ORDER BY dt.open_date_year, dt.open_date_month, dt.open_date_day,ts.sort_no
+         WHERE dt.open_date >= $1
+           AND dt.open_date < $2
+           AND dt.exec_status in ('WAIT', 'OPEN', 'EXEC', 'FAIL')
+         ORDER BY dt.open_date, ts.sort_no
     ",
     )
-    .bind(q_year)
-    .bind(q_month)
+    .bind(q_start_date)
+    .bind(q_end_date)
     .map(|row: PgRow| DailyTask {
         row_id: row.get("row_id"),
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         job_code: row.get("job_code"),
         exec_status: row.get("exec_status"),
+        last_error: row.get("last_error"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
         Ok(rows) => rows,
@@ -312,9 +343,12 @@ pub async fn find_all_by_exec_asc(q_year: &str, q_month: &str) -> Vec<DailyTask>
     }
 }
 
-pub async fn find_all_by_exec_desc(q_year: &str, q_month: &str) -> Vec<DailyTask> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_all_by_exec_desc(
+    repo: &Repository,
+    q_start_date: NaiveDate,
+    q_end_date: NaiveDate,
+) -> Vec<DailyTask> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -322,34 +356,36 @@ pub async fn find_all_by_exec_desc(q_year: &str, q_month: &str) -> Vec<DailyTask
              , dt.open_date_year
              , dt.open_date_month
              , dt.open_date_day
+             , dt.open_date
              , dt.job_code
              , dt.exec_status
+             , dt.last_error
           FROM daily_task dt
           JOIN calendar_data cd
-            ON dt.open_date_year = cd.ce_year
-           AND dt.open_date_month = cd.ce_month
-           AND dt.open_date_day = cd.ce_day
+            ON cd.ce_date = dt.open_date
           JOIN task_setting ts
             ON ts.group_code = cd.group_task 
            AND ts.job_code = dt.job_code
            AND ts.is_enabled = 1
-         WHERE dt.open_date_year = $1
-           AND dt.open_date_month = $2
-           AND dt.exec_status in ('WAIT', 'OPEN', 'EXEC')
-         ORDER BY dt.open_date_year desc, dt.open_date_month desc, dt.open_date_day desc,ts.sort_no
+         WHERE dt.open_date >= $1
+           AND dt.open_date < $2
+           AND dt.exec_status in ('WAIT', 'OPEN', 'EXEC', 'FAIL')
+         ORDER BY dt.open_date desc, ts.sort_no
     ",
     )
-    .bind(q_year)
-    .bind(q_month)
+    .bind(q_start_date)
+    .bind(q_end_date)
     .map(|row: PgRow| DailyTask {
         row_id: row.get("row_id"),
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         job_code: row.get("job_code"),
         exec_status: row.get("exec_status"),
+        last_error: row.get("last_error"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
         Ok(rows) => rows,
@@ -359,3 +395,113 @@ pub async fn find_all_by_exec_desc(q_year: &str, q_month: &str) -> Vec<DailyTask
         }
     }
 }
+
+pub async fn find_all_by_plan(
+    repo: &Repository,
+    flow_code: &str,
+    q_start_date: Option<NaiveDate>,
+    q_end_date: Option<NaiveDate>,
+) -> Vec<DailyTask> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        SELECT dt.row_id
+             , dt.open_date_year
+             , dt.open_date_month
+             , dt.open_date_day
+             , dt.open_date
+             , dt.job_code
+             , dt.exec_status
+             , dt.last_error
+          FROM daily_task dt
+          JOIN calendar_data cd
+            ON cd.ce_date = dt.open_date
+          JOIN task_setting ts
+            ON ts.group_code = cd.group_task 
+           AND ts.job_code = dt.job_code
+           AND ts.is_enabled = 1
+         WHERE dt.exec_status in ('WAIT', 'OPEN', 'EXEC', 'FAIL')
+           AND NOT EXISTS (
+               SELECT 1 
+                 FROM listen_flow lf
+                WHERE lf.flow_code = $1
+                  AND lf.flow_param1 = dt.open_date_year
+                  AND lf.flow_param2 = dt.open_date_month
+                  AND (lf.pstatus = 'EXIT' OR lf.lease_until >= now())
+            )
+           AND ($2::date IS NULL OR dt.open_date >= $2)
+           AND ($3::date IS NULL OR dt.open_date < $3)
+         ORDER BY dt.open_date, ts.sort_no
+    ",
+    )
+    .bind(flow_code)
+    .bind(q_start_date)
+    .bind(q_end_date)
+    .map(|row: PgRow| DailyTask {
+        row_id: row.get("row_id"),
+        open_date_year: row.get("open_date_year"),
+        open_date_month: row.get("open_date_month"),
+        open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
+        job_code: row.get("job_code"),
+        exec_status: row.get("exec_status"),
+        last_error: row.get("last_error"),
+    })
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "daily_task.find_all_by_plan: {}", &e);
+            Vec::new()
+        }
+    }
+}
+
+pub async fn find_all_by_job(repo: &Repository, q_job_code: &str) -> Vec<DailyTask> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        SELECT dt.row_id
+             , dt.open_date_year
+             , dt.open_date_month
+             , dt.open_date_day
+             , dt.open_date
+             , dt.job_code
+             , dt.exec_status
+             , dt.last_error
+          FROM daily_task dt
+          JOIN calendar_data cd
+            ON dt.open_date = cd.ce_date
+          JOIN task_setting ts
+            ON ts.group_code = cd.group_task 
+           AND ts.job_code = dt.job_code
+           AND ts.is_enabled = 1
+         WHERE dt.job_code = $1
+           AND dt.exec_status in ('WAIT', 'OPEN', 'EXEC')
+         ORDER BY dt.open_date
+    ",
+    )
+    .bind(q_job_code)
+    .map(|row: PgRow| DailyTask {
+        row_id: row.get("row_id"),
+        job_code: row.get("job_code"),
+        exec_status: row.get("exec_status"),
+        last_error: row.get("last_error"),
+        open_date_year: row.get("open_date_year"),
+        open_date_month: row.get("open_date_month"),
+        open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
+    })
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "daily_task.find_all_by_job: {}", &e);
+            Vec::new()
+        }
+    }
+}
@@ -1,13 +1,17 @@
 #![warn(clippy::all, clippy::pedantic)]
 
+use chrono::NaiveDate;
+
 #[derive(Debug, Clone)]
 pub struct DailyTask {
     pub row_id: String,
     pub open_date_year: String,
     pub open_date_month: String,
     pub open_date_day: String,
+    pub open_date: NaiveDate,
     pub job_code: String,
     pub exec_status: String,
+    pub last_error: String,
 }
 
 impl std::fmt::Display for DailyTask {
@@ -21,8 +25,27 @@ impl std::fmt::Display for DailyTask {
 
         write!(
             f,
-            "{0}, open_date: {1}{2}{3}, job_code: {4}, exec_status: {5}",
-            row_id, open_date_year, open_date_month, open_date_day, job_code, exec_status
+            "{row_id}, open_date: {open_date_year}{open_date_month}{open_date_day}, job_code: {job_code}, exec_status: {exec_status}"
+        )
+    }
+}
+
+/// 任務執行結果
+#[derive(Debug, Clone, Default)]
+pub struct JobSummary {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl std::fmt::Display for JobSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let succeeded = self.succeeded.len();
+        let failed = self.failed.len();
+        let failed_jobs = self.failed.join("; ");
+
+        write!(
+            f,
+            "succeeded: {succeeded}, failed: {failed}, failed_jobs: [{failed_jobs}]"
         )
     }
 }
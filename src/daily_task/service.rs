@@ -1,236 +1,477 @@
 #![warn(clippy::all, clippy::pedantic)]
-use std::{future::Future, pin::Pin, process};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
+use chrono::{Datelike, Days, Months, NaiveDate};
+use tokio::task::{Id, JoinSet};
 use tracing::{event, Level};
 
 use crate::{
-    listen_flow::{self, model::ListenFlow},
-    response_data, security_price, security_task, security_temp,
+    dry_run,
+    job::{model::Job, service::JobRegistry},
+    job_run::{self, model::JobCounter},
+    listen_flow, metrics,
+    repository::Repository,
+    task_dependency, Error, TaskOption,
+};
+
+use super::{
+    dao,
+    model::{DailyTask, JobSummary},
 };
 
-use super::{dao, model::DailyTask};
+pub async fn insert_task_data(
+    repo: &Repository,
+    registry: &JobRegistry,
+    open_date: NaiveDate,
+    dry_run: bool,
+) -> Result<(), Error> {
+    let mut unknown_jobs = Vec::<String>::new();
 
-pub async fn insert_task_data() -> Result<(), sqlx::Error> {
-    let task_list = dao::find_all().await;
+    let task_list = dao::find_all(repo, open_date).await;
     for data in task_list {
         event!(target: "security_api", Level::DEBUG, "DailyTask: {}", &data);
 
+        if !registry.contains(&data.job_code) {
+            event!(target: "security_api", Level::ERROR, "daily_task.insert_task_data unknown job_code {}", &data.job_code);
+            if !unknown_jobs.contains(&data.job_code) {
+                unknown_jobs.push(data.job_code);
+            }
+            continue;
+        }
+
         let q_year = &data.open_date_year;
         let q_month = &data.open_date_month;
         let q_day = &data.open_date_day;
         let q_job_code = &data.job_code;
 
-        let task = dao::find_one(q_year, q_month, q_day, q_job_code).await;
-        if task.is_none() {
+        let task = dao::find_one(repo, q_year, q_month, q_day, q_job_code).await;
+        if task.is_none() && dry_run {
+            dry_run::insert(
+                "daily_task",
+                &format!("{} {}", data.open_date, data.job_code),
+            );
+        } else if task.is_none() {
             let new_date = DailyTask {
                 open_date_year: data.open_date_year,
                 open_date_month: data.open_date_month,
                 open_date_day: data.open_date_day,
+                open_date: data.open_date,
                 job_code: data.job_code,
                 exec_status: "WAIT".to_string(),
-                row_id: "".to_string(),
+                last_error: String::new(),
+                row_id: String::new(),
             };
-            dao::create(new_date).await?;
+            dao::create(repo, new_date).await?;
         }
     }
-    Ok(())
+
+    if unknown_jobs.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::UnknownJob(unknown_jobs.join(", ")))
+    }
 }
 
-pub async fn exec_daily_task() -> Result<(), sqlx::Error> {
-    let mut exec_task = dao::find_one_by_exec_desc("security").await;
-    while exec_task.is_some() {
-        let e_open_date = start_open_data("security", &exec_task.unwrap()).await;
-
-        let (year, month) = e_open_date.await;
-
-        let task_list = dao::find_all_by_exec_desc(&year, &month).await;
-        for task in task_list {
-            event!(target: "security_api", Level::INFO, "DailyTaskInfo: {0}", &task);
-            update_task_status(&task, "OPEN").await;
-
-            let job_code = &task.job_code;
-            let ref_job_code = job_code.as_str();
-
-            // 執行任務
-            match ref_job_code {
-                "delete_temp" => init_security_data(&task).await,
-                "get_web_security" => reply_security_data(&task).await,
-                "res_to_temp" => response_to_temp(&task).await,
-                "temp_to_task" => temp_to_daily_security(&task).await,
-                "task_run" => executive_daily_security(&task).await,
-                _ => (),
-            }
-        }
+pub async fn exec_daily_task(
+    repo: &Repository,
+    registry: &JobRegistry,
+    option: &TaskOption,
+) -> Result<JobSummary, Error> {
+    let (q_start_date, q_end_date) = month_filter(option)?;
+    let dependencies = task_dependency::service::find_dependencies(repo, registry).await?;
+    let mut summary = JobSummary::default();
+
+    let mut exec_task =
+        dao::find_one_by_exec_desc(repo, "security", q_start_date, q_end_date).await;
+    while let Some(open_task) = exec_task {
+        let year = &open_task.open_date_year;
+        let month = &open_task.open_date_month;
+
+        if let Some(row_id) =
+            listen_flow::service::claim_flow_data(repo, "security", year, month).await?
+        {
+            let heartbeat = listen_flow::service::start_heartbeat(repo, &row_id);
 
-        end_open_date("security", &year, &month).await;
-        exec_task = dao::find_one_by_exec_desc("security").await;
+            let (start_date, end_date) = month_range(open_task.open_date);
+            let task_list = dao::find_all_by_exec_desc(repo, start_date, end_date).await;
+            run_task_list(
+                repo,
+                registry,
+                &dependencies,
+                "security",
+                &task_list,
+                option,
+                &mut summary,
+            )
+            .await;
+
+            heartbeat.abort();
+            listen_flow::service::end_flow_data(repo, &row_id).await?;
+        }
+        exec_task = dao::find_one_by_exec_desc(repo, "security", q_start_date, q_end_date).await;
     }
-    Ok(())
+    Ok(summary)
 }
 
-pub async fn exec_price_task() -> Result<(), Box<dyn std::error::Error>> {
-    let mut exec_task = dao::find_one_by_exec_asc("price").await;
-    while exec_task.is_some() {
-        let e_open_date = start_open_data("price", &exec_task.unwrap()).await;
+pub async fn exec_price_task(
+    repo: &Repository,
+    registry: &JobRegistry,
+    option: &TaskOption,
+) -> Result<JobSummary, Error> {
+    let (q_start_date, q_end_date) = month_filter(option)?;
+    let dependencies = task_dependency::service::find_dependencies(repo, registry).await?;
+    let mut summary = JobSummary::default();
 
-        let (year, month) = e_open_date.await;
+    let mut exec_task = dao::find_one_by_exec_asc(repo, "price", q_start_date, q_end_date).await;
+    while let Some(open_task) = exec_task {
+        let year = &open_task.open_date_year;
+        let month = &open_task.open_date_month;
 
-        let task_list = dao::find_all_by_exec_asc(&year, &month).await;
-        for task in task_list {
-            event!(target: "security_api", Level::INFO, "DailyTaskInfo {0}", &task);
-            update_task_status(&task, "OPEN").await;
+        if let Some(row_id) =
+            listen_flow::service::claim_flow_data(repo, "price", year, month).await?
+        {
+            let heartbeat = listen_flow::service::start_heartbeat(repo, &row_id);
 
-            let job_code = &task.job_code;
-            let ref_job_code = job_code.as_str();
+            let (start_date, end_date) = month_range(open_task.open_date);
+            let task_list = dao::find_all_by_exec_asc(repo, start_date, end_date).await;
+            run_task_list(
+                repo,
+                registry,
+                &dependencies,
+                "price",
+                &task_list,
+                option,
+                &mut summary,
+            )
+            .await;
 
-            // 執行任務
-            match ref_job_code {
-                "res_price" => parse_security_price(&task).await,
-                "price_value" => statistics_average_price(&task).await,
-                _ => (),
-            }
+            heartbeat.abort();
+            listen_flow::service::end_flow_data(repo, &row_id).await?;
         }
-
-        end_open_date("price", &year, &month).await;
-        exec_task = dao::find_one_by_exec_asc("price").await;
+        exec_task = dao::find_one_by_exec_asc(repo, "price", q_start_date, q_end_date).await;
     }
-    Ok(())
+    Ok(summary)
 }
 
-async fn init_security_data(task: &DailyTask) {
-    match security_temp::service::delete_temp().await {
-        Ok(_) => {
-            update_task_status(task, "EXIT").await;
-            event!(target: "security_api", Level::INFO, "daily_task.delete_temp Done");
+/// 依開市日執行當月任務，同一開市日依相依關係分批平行執行 (上游失敗時略過下游)
+async fn run_task_list(
+    repo: &Repository,
+    registry: &JobRegistry,
+    dependencies: &HashMap<String, Vec<String>>,
+    flow_code: &str,
+    task_list: &[DailyTask],
+    option: &TaskOption,
+    summary: &mut JobSummary,
+) {
+    let mut open_dates = Vec::<NaiveDate>::new();
+    for task in task_list {
+        if !open_dates.contains(&task.open_date) {
+            open_dates.push(task.open_date);
         }
-        Err(e) => {
-            update_task_status(task, "EXEC").await;
-            event!(target: "security_api", Level::ERROR, "daily_task.delete_temp {}", &e);
-            panic!("daily_task.delete_temp Error {}", &e)
+    }
+
+    for open_date in open_dates {
+        let mut pending = Vec::<(DailyTask, Arc<dyn Job>)>::new();
+        let mut failed_jobs = HashSet::<String>::new();
+        for task in task_list.iter().filter(|x| x.open_date == open_date) {
+            let Some(job) = registry.get(&task.job_code) else {
+                let e = Error::UnknownJob(task.job_code.clone());
+                end_task(repo, task, Err(e), summary).await;
+                failed_jobs.insert(task.job_code.clone());
+                continue;
+            };
+            if job.flow_code() == flow_code {
+                pending.push((task.clone(), Arc::clone(job)));
+            }
         }
+        run_task_graph(repo, dependencies, pending, failed_jobs, option, summary).await;
     }
 }
 
-async fn reply_security_data(task: &DailyTask) {
-    match response_data::service::get_security_all_code(task).await {
-        Ok(_) => {
-            update_task_status(task, "EXIT").await;
-            event!(target: "security_api", Level::INFO, "daily_task.get_web_security Done");
+/// 依相依關係分批執行同一開市日的任務
+async fn run_task_graph(
+    repo: &Repository,
+    dependencies: &HashMap<String, Vec<String>>,
+    mut pending: Vec<(DailyTask, Arc<dyn Job>)>,
+    mut failed_jobs: HashSet<String>,
+    option: &TaskOption,
+    summary: &mut JobSummary,
+) {
+    let mut done_jobs = HashSet::<String>::new();
+
+    while !pending.is_empty() {
+        let pending_jobs: HashSet<String> =
+            pending.iter().map(|(x, _)| x.job_code.clone()).collect();
+
+        let mut ready = Vec::<(DailyTask, Arc<dyn Job>)>::new();
+        let mut blocked = Vec::<(DailyTask, Arc<dyn Job>)>::new();
+        for (task, job) in pending {
+            let depend_codes = dependencies
+                .get(&task.job_code)
+                .map_or(&[][..], Vec::as_slice);
+
+            if let Some(dependency) = depend_codes.iter().find(|x| failed_jobs.contains(*x)) {
+                // 標記為失敗，重新執行時一併執行
+                let e = Error::JobFailed(format!("{} skipped, {dependency} failed", task.job_code));
+                end_task(repo, &task, Err(e), summary).await;
+                failed_jobs.insert(task.job_code.clone());
+            } else if depend_codes
+                .iter()
+                .any(|x| pending_jobs.contains(x) && !done_jobs.contains(x))
+            {
+                blocked.push((task, job));
+            } else if let Some(dependency) = wait_dependency(repo, &task, depend_codes).await {
+                let e = Error::JobFailed(format!(
+                    "{} skipped, {dependency} not finished",
+                    task.job_code
+                ));
+                end_task(repo, &task, Err(e), summary).await;
+                failed_jobs.insert(task.job_code.clone());
+            } else {
+                ready.push((task, job));
+            }
         }
-        Err(e) => {
-            update_task_status(task, "EXEC").await;
-            event!(target: "security_api", Level::ERROR, "daily_task.get_web_security {}", &e);
-            panic!("daily_task.get_web_security Error {}", &e)
+
+        if ready.is_empty() {
+            // 剩餘任務互相等待 (相依設定循環)
+            for (task, _) in blocked {
+                let e = Error::JobFailed(format!("{} dependency cycle", task.job_code));
+                end_task(repo, &task, Err(e), summary).await;
+            }
+            break;
         }
-    }
-}
 
-async fn response_to_temp(task: &DailyTask) {
-    match security_temp::service::get_security_to_temp(task).await {
-        Ok(_) => {
-            update_task_status(task, "EXIT").await;
-            event!(target: "security_api", Level::INFO, "daily_task.res_to_temp Done");
+        let mut join_set = JoinSet::new();
+        let mut running = HashMap::<Id, DailyTask>::new();
+        for (task, job) in ready {
+            let repo = repo.clone();
+            let option = option.clone();
+            let spawned_task = task.clone();
+            let handle = join_set.spawn(async move {
+                let result = run_job(&repo, &job, &spawned_task, &option).await;
+                (spawned_task, result)
+            });
+            running.insert(handle.id(), task);
         }
-        Err(e) => {
-            update_task_status(task, "EXEC").await;
-            event!(target: "security_api", Level::ERROR, "daily_task.res_to_temp {}", &e);
-            panic!("daily_task.res_to_temp Error {}", &e)
+        while let Some(joined) = join_set.join_next().await {
+            // 工作中止 (panic) 時同樣標記為失敗
+            let (task, result) = match joined {
+                Ok(joined) => joined,
+                Err(e) => {
+                    let Some(task) = running.remove(&e.id()) else {
+                        event!(target: "security_api", Level::ERROR, "daily_task.run_task_graph {}", &e);
+                        continue;
+                    };
+                    let message = format!("{} {e}", task.job_code);
+                    (task, Err(Error::JobFailed(message)))
+                }
+            };
+            if end_task(repo, &task, result, summary).await {
+                done_jobs.insert(task.job_code);
+            } else {
+                failed_jobs.insert(task.job_code);
+            }
         }
+
+        pending = blocked;
     }
 }
 
-async fn temp_to_daily_security(task: &DailyTask) {
-    match security_task::service::insert_task_data(task).await {
-        Ok(_) => {
-            update_task_status(task, "EXIT").await;
-            event!(target: "security_api", Level::INFO, "daily_task.temp_to_task Done");
-        }
-        Err(e) => {
-            update_task_status(task, "EXEC").await;
-            event!(target: "security_api", Level::ERROR, "daily_task.temp_to_task {}", &e);
-            panic!("daily_task.temp_to_task Error {}", &e)
-        }
+/// 執行工作並紀錄執行歷程 (耗時與筆數)
+async fn run_job(
+    repo: &Repository,
+    job: &Arc<dyn Job>,
+    task: &DailyTask,
+    option: &TaskOption,
+) -> Result<(), Error> {
+    event!(target: "security_api", Level::INFO, "DailyTaskInfo: {0}", task);
+    if !update_task_status(repo, task, "OPEN", "").await {
+        return Err(Error::JobFailed(format!("{} open", task.job_code)));
     }
 
-    security_task::service_range::update_task_data(task)
-        .await
-        .unwrap();
+    let counter = Arc::new(JobCounter::default());
+    let row_id = job_run::service::start_job_run(repo, job.flow_code(), task).await;
+    let result =
+        job_run::service::scope(Some(Arc::clone(&counter)), job.run(repo, task, option)).await;
+    if let Some(row_id) = row_id {
+        job_run::service::end_job_run(repo, &row_id, task, &counter, &result).await;
+    }
+    result
 }
 
-async fn executive_daily_security(task: &DailyTask) {
-    match security_task::service::get_all_task(task).await {
-        Ok(_) => {
-            update_task_status(task, "EXIT").await;
-            event!(target: "security_api", Level::INFO, "daily_task.task_run Done");
-        }
-        Err(e) => {
-            update_task_status(task, "EXEC").await;
-            event!(target: "security_api", Level::ERROR, "daily_task.task_run {}", &e);
-            panic!("daily_task.task_run Error {}", &e)
+/// 同一開市日尚未結束的相依工作 (不在本次執行清單者)
+async fn wait_dependency<'a>(
+    repo: &Repository,
+    task: &DailyTask,
+    depend_codes: &'a [String],
+) -> Option<&'a str> {
+    for dependency in depend_codes {
+        let q_year = &task.open_date_year;
+        let q_month = &task.open_date_month;
+        let q_day = &task.open_date_day;
+        let dependency_task = dao::find_one(repo, q_year, q_month, q_day, dependency).await;
+        if dependency_task.is_some_and(|x| x.exec_status != "EXIT") {
+            return Some(dependency);
         }
     }
+    None
 }
 
-async fn parse_security_price(task: &DailyTask) {
-    match security_price::service::get_security_to_price(task).await {
-        Ok(_) => {
-            update_task_status(task, "EXIT").await;
-            event!(target: "security_api", Level::INFO,  "daily_task.res_price Done");
-        }
-        Err(e) => {
-            update_task_status(task, "EXEC").await;
-            event!(target: "security_api", Level::ERROR, "daily_task.res_price {}", &e);
-            panic!("daily_task.res_price Error {}", &e)
+/// 執行單一工作的待執行任務 (相依工作未結束的開市日、其他程序處理中的年月略過)
+pub async fn exec_job_task(
+    repo: &Repository,
+    registry: &JobRegistry,
+    job_code: &str,
+    option: &TaskOption,
+) -> Result<JobSummary, Error> {
+    let job = registry
+        .get(job_code)
+        .ok_or_else(|| Error::UnknownJob(job_code.to_string()))?;
+    let dependencies = task_dependency::service::find_dependencies(repo, registry).await?;
+    let depend_codes = dependencies.get(job_code).map_or(&[][..], Vec::as_slice);
+    let mut summary = JobSummary::default();
+
+    let task_list = dao::find_all_by_job(repo, job_code).await;
+    for month_tasks in task_list.chunk_by(|a, b| {
+        (&a.open_date_year, &a.open_date_month) == (&b.open_date_year, &b.open_date_month)
+    }) {
+        let year = &month_tasks[0].open_date_year;
+        let month = &month_tasks[0].open_date_month;
+
+        // 與 run_* 及其他排程共用流程年月租約，處理中的年月留待下次
+        let Some(row_id) =
+            listen_flow::service::lease_flow_data(repo, job.flow_code(), year, month).await?
+        else {
+            event!(target: "security_api", Level::DEBUG, "daily_task.{} {}/{} leased by another process", job_code, year, month);
+            continue;
+        };
+        let heartbeat = listen_flow::service::start_heartbeat(repo, &row_id);
+
+        for task in month_tasks {
+            if let Some(dependency) = wait_dependency(repo, task, depend_codes).await {
+                event!(target: "security_api", Level::DEBUG, "daily_task.{} {} wait {}", job_code, &task.open_date, dependency);
+                continue;
+            }
+
+            let result = run_job(repo, job, task, option).await;
+            end_task(repo, task, result, &mut summary).await;
         }
+
+        heartbeat.abort();
+        listen_flow::service::release_flow_data(repo, &row_id).await?;
+    }
+    Ok(summary)
+}
+
+/// 輸出執行結果 (有失敗任務時回傳錯誤)
+pub fn check_summary(flow_code: &str, summary: &JobSummary) -> Result<(), Error> {
+    event!(target: "security_api", Level::INFO, "{} summary {}", flow_code, summary);
+
+    if summary.failed.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::JobFailed(format!(
+            "{flow_code} {} failed",
+            summary.failed.len()
+        )))
     }
 }
 
-async fn statistics_average_price(task: &DailyTask) {
-    match security_price::service::get_calculator_to_price(task).await {
-        Ok(_) => {
-            update_task_status(task, "EXIT").await;
-            event!(target: "security_api", Level::INFO,  "daily_task.price_value Done");
+/// 列出待執行任務與將寫入的資料 (不寫入資料庫)
+pub async fn plan_daily_task(
+    repo: &Repository,
+    registry: &JobRegistry,
+    flow_code: &str,
+    option: &TaskOption,
+) -> Result<(), Error> {
+    let (q_start_date, q_end_date) = month_filter(option)?;
+
+    let task_list = dao::find_all_by_plan(repo, flow_code, q_start_date, q_end_date).await;
+    for task in task_list {
+        let job = registry.get(&task.job_code);
+        if job.is_some_and(|x| x.flow_code() != flow_code) {
+            continue;
         }
-        Err(e) => {
-            update_task_status(task, "EXEC").await;
-            event!(target: "security_api", Level::ERROR,  "daily_task.price_value {}", &e);
-            panic!("daily_task.price_value Error {}", &e)
+
+        dry_run::header(&format!(
+            "{flow_code} {} {} {}",
+            task.open_date, task.job_code, task.exec_status
+        ));
+        if let Some(job) = job {
+            if let Err(e) = job.plan(repo, &task, option).await {
+                event!(target: "security_api", Level::ERROR, "daily_task.plan {} {}", &task.job_code, &e);
+            }
         }
     }
+    Ok(())
 }
 
-async fn update_task_status(task: &DailyTask, status: &str) {
-    let mut daily_task = task.clone();
-    daily_task.exec_status = status.to_string();
+/// 指定年月條件 (當月第一天至下月第一天，不含)
+fn month_filter(option: &TaskOption) -> Result<(Option<NaiveDate>, Option<NaiveDate>), Error> {
+    let Some((year, month)) = option.open_month else {
+        return Ok((None, None));
+    };
+    let date = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| Error::Parse(format!("month {year}-{month:02}")))?;
+    let (start_date, end_date) = month_range(date);
+    Ok((Some(start_date), Some(end_date)))
+}
 
-    dao::modify(daily_task).await.unwrap();
+/// 所屬月份範圍 (當月第一天至下月第一天，不含)
+fn month_range(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let start_date = date - Days::new(u64::from(date.day0()));
+    (start_date, start_date + Months::new(1))
 }
 
-async fn start_open_data(
-    flow_code: &str,
+/// 結束任務 (失敗時標記為 FAIL 並紀錄錯誤，回傳是否成功)
+async fn end_task(
+    repo: &Repository,
     task: &DailyTask,
-) -> Pin<Box<dyn Future<Output = (String, String)>>> {
-    let pid = process::id() as i32;
-    let year = &task.open_date_year;
-    let month = &task.open_date_month;
-
-    let results = listen_flow::service::read_flow_data(flow_code, &year, &month).await;
-    let current_tasks = results
-        .into_iter()
-        .filter(|x| x.pid == pid && x.pstatus != "EXIT".to_string())
-        .collect::<Vec<ListenFlow>>();
-    if current_tasks.len() > 0 {
-        let exec_task = dao::find_one_by_exec_asc(flow_code).await;
-        return Box::pin(start_open_data(flow_code, &exec_task.unwrap())).await;
-    } else {
-        listen_flow::service::insert_flow_data2(pid, flow_code, &year, &month).await;
-        let y = year.clone();
-        let m = month.clone();
-        return Box::pin(async move { (y, m) });
+    result: Result<(), Error>,
+    summary: &mut JobSummary,
+) -> bool {
+    let job_code = &task.job_code;
+
+    match result {
+        Ok(()) => {
+            event!(target: "security_api", Level::INFO, "daily_task.{} Done", job_code);
+            summary
+                .succeeded
+                .push(format!("{} {job_code}", task.open_date));
+            metrics::service::add_job_run(job_code, "EXIT");
+            update_task_status(repo, task, "EXIT", "").await
+        }
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "daily_task.{} {}", job_code, &e);
+            summary
+                .failed
+                .push(format!("{} {job_code}: {e}", task.open_date));
+            metrics::service::add_job_run(job_code, "FAIL");
+            update_task_status(repo, task, "FAIL", &e.to_string()).await;
+            false
+        }
     }
 }
 
-async fn end_open_date(flow_code: &str, year: &str, month: &str) {
-    let pid = process::id() as i32;
-    listen_flow::service::modify_flow_data2(pid, flow_code, year, month).await;
+/// 更新任務狀態 (回傳是否成功)
+async fn update_task_status(
+    repo: &Repository,
+    task: &DailyTask,
+    status: &str,
+    error: &str,
+) -> bool {
+    let mut daily_task = task.clone();
+    daily_task.exec_status = status.to_string();
+    daily_task.last_error = error.to_string();
+
+    match dao::modify(repo, daily_task).await {
+        Ok(_) => true,
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "daily_task.update_task_status {}", &e);
+            false
+        }
+    }
 }
@@ -2,10 +2,23 @@ use std::process::Command;
 
 use chrono::Local;
 
+use crate::config;
+
+/// 不備份的資料表
+fn exclude_tables() -> Vec<String> {
+    config::service::get()
+        .backup
+        .exclude_tables
+        .iter()
+        .map(|x| format!("--exclude-table={x}"))
+        .collect()
+}
+
 pub struct DatabaseBackup;
 
+#[allow(clippy::unused_self)]
 impl DatabaseBackup {
-    pub fn backup_insert(self: Self, database_name: &str, backup_path: &str) {
+    pub fn backup_insert(self, database_name: &str, backup_path: &str) {
         let output = Command::new("pg_dump")
             .arg(database_name)
             .arg(format!(
@@ -18,22 +31,20 @@ impl DatabaseBackup {
             .arg("--format=plain")
             .arg("--column-inserts")
             .arg("--no-sync")
-            .arg("--exclude-table=_sqlx_migrations")
-            .arg("--exclude-table=listen_flow")
-            .arg("--exclude-table=security_temp")
+            .args(exclude_tables())
             .output()
             .expect("Failed to execute command");
 
         if output.status.success() {
             let stdout = String::from_utf8_lossy(&output.stdout);
-            println!("Output: {}", stdout);
+            println!("Output: {stdout}");
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            eprintln!("Error: {}", stderr);
+            eprintln!("Error: {stderr}");
         }
     }
 
-    pub fn backup_copy(self: Self, database_name: &str, backup_path: &str) {
+    pub fn backup_copy(self, database_name: &str, backup_path: &str) {
         let output = Command::new("pg_dump")
             .arg(database_name)
             .arg(format!(
@@ -45,18 +56,16 @@ impl DatabaseBackup {
             .arg("--encoding=UTF-8")
             .arg("--format=plain")
             .arg("--no-sync")
-            .arg("--exclude-table=_sqlx_migrations")
-            .arg("--exclude-table=listen_flow")
-            .arg("--exclude-table=security_temp")
+            .args(exclude_tables())
             .output()
             .expect("Failed to execute command");
 
         if output.status.success() {
             let stdout = String::from_utf8_lossy(&output.stdout);
-            println!("Output: {}", stdout);
+            println!("Output: {stdout}");
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            eprintln!("Error: {}", stderr);
+            eprintln!("Error: {stderr}");
         }
     }
 }
@@ -0,0 +1,16 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+/// 試跑報告標題 (其下 `+` 為新增、`-` 為刪除，修改以刪除舊值與新增新值表示)
+pub fn header(title: &str) {
+    println!("@@ {title} @@");
+}
+
+/// 將新增的資料
+pub fn insert(table: &str, row: &str) {
+    println!("+ {table} {row}");
+}
+
+/// 將刪除的資料
+pub fn delete(table: &str, row: &str) {
+    println!("- {table} {row}");
+}
@@ -0,0 +1,59 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+/// 共用錯誤
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// 網路連線失敗
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+    /// 資料解析失敗
+    #[error("parse error: {0}")]
+    Parse(String),
+    /// 資料庫錯誤
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    /// 來源網站拒絕
+    #[error("upstream rejected: {0}")]
+    UpstreamRejected(String),
+    /// 來源網站限制流量
+    #[error("rate limited: {0}")]
+    RateLimited(String),
+    /// 檔案讀寫失敗
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// 排程任務失敗
+    #[error("job failed: {0}")]
+    JobFailed(String),
+    /// 未註冊的工作代碼
+    #[error("unknown job: {0}")]
+    UnknownJob(String),
+    /// 設定錯誤
+    #[error("config error: {0}")]
+    Config(String),
+}
+
+impl Error {
+    /// 程式結束代碼
+    #[must_use]
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Database(_) => 3,
+            Error::Http(_) => 4,
+            Error::UpstreamRejected(_) => 5,
+            Error::RateLimited(_) => 6,
+            Error::Parse(_) => 7,
+            Error::Io(_) => 8,
+            Error::JobFailed(_) => 9,
+            Error::UnknownJob(_) => 10,
+            Error::Config(_) => 11,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Parse(e.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
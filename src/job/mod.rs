@@ -0,0 +1,4 @@
+pub mod model;
+pub mod price;
+pub mod security;
+pub mod service;
@@ -0,0 +1,40 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::{future::Future, pin::Pin};
+
+use crate::{daily_task::model::DailyTask, repository::Repository, Error, TaskOption};
+
+/// 工作執行結果
+pub type JobFuture<'a> = Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>>;
+
+/// 排程工作 (對應 `task_setting.job_code`)
+pub trait Job: Send + Sync {
+    /// 工作代碼
+    fn name(&self) -> &'static str;
+
+    /// 流程代碼 (security/price)
+    fn flow_code(&self) -> &'static str;
+
+    /// 相依工作 (同一開市日需先結束，執行前寫入 `task_dependency`)
+    fn dependencies(&self) -> &[&str] {
+        &[]
+    }
+
+    /// 執行工作
+    fn run<'a>(
+        &'a self,
+        repo: &'a Repository,
+        task: &'a DailyTask,
+        option: &'a TaskOption,
+    ) -> JobFuture<'a>;
+
+    /// 試跑 (列出將寫入的資料，不寫入資料庫)，未實作的工作僅列出任務
+    fn plan<'a>(
+        &'a self,
+        _repo: &'a Repository,
+        _task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
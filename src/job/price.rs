@@ -0,0 +1,79 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use crate::{daily_task::model::DailyTask, repository::Repository, security_price, TaskOption};
+
+use super::model::{Job, JobFuture};
+
+/// 回應資料轉收盤價
+pub struct ResPrice;
+
+impl Job for ResPrice {
+    fn name(&self) -> &'static str {
+        "res_price"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "price"
+    }
+
+    fn run<'a>(
+        &'a self,
+        repo: &'a Repository,
+        task: &'a DailyTask,
+        option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(security_price::service::get_security_to_price(
+            repo,
+            task,
+            option.dry_run,
+        ))
+    }
+
+    fn plan<'a>(
+        &'a self,
+        repo: &'a Repository,
+        task: &'a DailyTask,
+        option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        self.run(repo, task, option)
+    }
+}
+
+/// 計算平均價格
+pub struct PriceValue;
+
+impl Job for PriceValue {
+    fn name(&self) -> &'static str {
+        "price_value"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "price"
+    }
+
+    fn dependencies(&self) -> &[&str] {
+        &["res_price"]
+    }
+
+    fn run<'a>(
+        &'a self,
+        repo: &'a Repository,
+        task: &'a DailyTask,
+        option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(security_price::service::get_calculator_to_price(
+            repo,
+            task,
+            option.dry_run,
+        ))
+    }
+
+    fn plan<'a>(
+        &'a self,
+        repo: &'a Repository,
+        task: &'a DailyTask,
+        option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        self.run(repo, task, option)
+    }
+}
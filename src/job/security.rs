@@ -0,0 +1,150 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use crate::{
+    daily_task::model::DailyTask, repository::Repository, response_data, security_task,
+    security_temp, TaskOption,
+};
+
+use super::model::{Job, JobFuture};
+
+/// 清除證券暫存
+pub struct DeleteTemp;
+
+impl Job for DeleteTemp {
+    fn name(&self) -> &'static str {
+        "delete_temp"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "security"
+    }
+
+    fn run<'a>(
+        &'a self,
+        repo: &'a Repository,
+        _task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(security_temp::service::delete_temp(repo))
+    }
+}
+
+/// 下載證券代碼
+pub struct GetWebSecurity;
+
+impl Job for GetWebSecurity {
+    fn name(&self) -> &'static str {
+        "get_web_security"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "security"
+    }
+
+    fn dependencies(&self) -> &[&str] {
+        &["delete_temp"]
+    }
+
+    fn run<'a>(
+        &'a self,
+        repo: &'a Repository,
+        task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(response_data::service::get_security_all_code(repo, task))
+    }
+}
+
+/// 證券代碼轉暫存
+pub struct ResToTemp;
+
+impl Job for ResToTemp {
+    fn name(&self) -> &'static str {
+        "res_to_temp"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "security"
+    }
+
+    fn dependencies(&self) -> &[&str] {
+        &["get_web_security"]
+    }
+
+    fn run<'a>(
+        &'a self,
+        repo: &'a Repository,
+        task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(security_temp::service::get_security_to_temp(repo, task))
+    }
+}
+
+/// 暫存轉證券任務
+pub struct TempToTask;
+
+impl Job for TempToTask {
+    fn name(&self) -> &'static str {
+        "temp_to_task"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "security"
+    }
+
+    fn dependencies(&self) -> &[&str] {
+        &["res_to_temp"]
+    }
+
+    fn run<'a>(
+        &'a self,
+        repo: &'a Repository,
+        task: &'a DailyTask,
+        option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(async move {
+            security_task::service::insert_task_data(repo, task, option.dry_run).await?;
+            security_task::service_range::update_task_data(repo, task, option.dry_run).await
+        })
+    }
+
+    fn plan<'a>(
+        &'a self,
+        repo: &'a Repository,
+        task: &'a DailyTask,
+        option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        self.run(repo, task, option)
+    }
+}
+
+/// 執行證券任務 (下載價格)
+pub struct TaskRun;
+
+impl Job for TaskRun {
+    fn name(&self) -> &'static str {
+        "task_run"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "security"
+    }
+
+    fn dependencies(&self) -> &[&str] {
+        &["temp_to_task"]
+    }
+
+    fn run<'a>(
+        &'a self,
+        repo: &'a Repository,
+        task: &'a DailyTask,
+        option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(security_task::service::get_all_task(
+            repo,
+            task,
+            &option.market_types,
+        ))
+    }
+}
@@ -0,0 +1,63 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::{collections::HashMap, sync::Arc};
+
+use super::{
+    model::Job,
+    price::{PriceValue, ResPrice},
+    security::{DeleteTemp, GetWebSecurity, ResToTemp, TaskRun, TempToTask},
+};
+
+/// 已註冊的排程工作
+#[derive(Clone)]
+pub struct JobRegistry {
+    jobs: HashMap<&'static str, Arc<dyn Job>>,
+}
+
+impl JobRegistry {
+    /// 建立含內建工作的註冊表
+    #[must_use]
+    pub fn new() -> Self {
+        let mut registry = JobRegistry {
+            jobs: HashMap::new(),
+        };
+        registry
+            .register(DeleteTemp)
+            .register(GetWebSecurity)
+            .register(ResToTemp)
+            .register(TempToTask)
+            .register(TaskRun)
+            .register(ResPrice)
+            .register(PriceValue);
+        registry
+    }
+
+    /// 註冊工作 (同名時取代)
+    pub fn register(&mut self, job: impl Job + 'static) -> &mut Self {
+        self.jobs.insert(job.name(), Arc::new(job));
+        self
+    }
+
+    /// 依工作代碼取得工作
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn Job>> {
+        self.jobs.get(name)
+    }
+
+    /// 已註冊的工作代碼
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.jobs.keys().copied()
+    }
+
+    /// 是否已註冊
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.jobs.contains_key(name)
+    }
+}
+
+impl Default for JobRegistry {
+    fn default() -> Self {
+        JobRegistry::new()
+    }
+}
@@ -0,0 +1,161 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use sqlx::{postgres::PgRow, Row};
+use tracing::{event, Level};
+
+use crate::repository::Repository;
+
+use super::model::JobRun;
+
+/// 新增執行歷程 (回傳序號)
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn create(repo: &Repository, data: JobRun) -> Result<String, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        INSERT INTO job_run(
+            flow_code
+          , job_code
+          , open_date
+          , owner
+          , start_time
+          , exec_status
+          , created_date
+          , updated_date
+        ) VALUES ( $1, $2, $3, $4, now(), $5, now(), now() )
+        RETURNING row_id
+    ",
+    )
+    .bind(data.flow_code)
+    .bind(data.job_code)
+    .bind(data.open_date)
+    .bind(data.owner)
+    .bind(data.exec_status)
+    .map(|row: PgRow| row.get::<String, _>("row_id"))
+    .fetch_one(conn)
+    .await
+    {
+        Ok(row_id) => Ok(row_id),
+        Err(e) => Err(e),
+    }
+}
+
+/// 結束執行歷程
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn modify_by_end(repo: &Repository, data: JobRun) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        UPDATE job_run 
+           SET end_time = now()
+             , exec_status = $1
+             , insert_count = $2
+             , update_count = $3
+             , skip_count = $4
+             , request_count = $5
+             , last_error = $6
+             , updated_date = now()
+         WHERE row_id = $7
+    ",
+    )
+    .bind(data.exec_status)
+    .bind(data.insert_count)
+    .bind(data.update_count)
+    .bind(data.skip_count)
+    .bind(data.request_count)
+    .bind(data.last_error)
+    .bind(data.row_id)
+    .execute(conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn find_all_by_recent(
+    repo: &Repository,
+    q_job_code: Option<&str>,
+    q_limit: i64,
+) -> Vec<JobRun> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        SELECT row_id
+             , flow_code
+             , job_code
+             , open_date
+             , owner
+             , start_time
+             , end_time
+             , exec_status
+             , insert_count
+             , update_count
+             , skip_count
+             , request_count
+             , last_error
+          FROM job_run
+         WHERE ($1::varchar IS NULL OR job_code = $1)
+         ORDER BY start_time desc
+         LIMIT $2
+    ",
+    )
+    .bind(q_job_code)
+    .bind(q_limit)
+    .map(|row: PgRow| JobRun {
+        row_id: row.get("row_id"),
+        flow_code: row.get("flow_code"),
+        job_code: row.get("job_code"),
+        open_date: row.get("open_date"),
+        owner: row.get("owner"),
+        start_time: row.get("start_time"),
+        end_time: row.get("end_time"),
+        exec_status: row.get("exec_status"),
+        insert_count: row.get("insert_count"),
+        update_count: row.get("update_count"),
+        skip_count: row.get("skip_count"),
+        request_count: row.get("request_count"),
+        last_error: row.get("last_error"),
+    })
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "job_run.find_all_by_recent: {}", &e);
+            Vec::new()
+        }
+    }
+}
+
+/// 各工作最後成功時間 (epoch 秒)
+pub async fn find_all_by_last_success(repo: &Repository) -> Vec<(String, f64)> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        SELECT job_code
+             , extract(epoch FROM max(end_time)::timestamptz)::float8 AS last_success
+          FROM job_run
+         WHERE exec_status = 'EXIT'
+         GROUP BY job_code
+    ",
+    )
+    .map(|row: PgRow| (row.get("job_code"), row.get("last_success")))
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "job_run.find_all_by_last_success: {}", &e);
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,65 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+#[derive(Debug, Clone)]
+pub struct JobRun {
+    pub row_id: String,
+    pub flow_code: String,
+    pub job_code: String,
+    pub open_date: NaiveDate,
+    pub owner: String,
+    pub start_time: NaiveDateTime,
+    pub end_time: Option<NaiveDateTime>,
+    pub exec_status: String,
+    pub insert_count: i64,
+    pub update_count: i64,
+    pub skip_count: i64,
+    pub request_count: i64,
+    pub last_error: String,
+}
+
+impl std::fmt::Display for JobRun {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let start_time = self.start_time.format("%Y-%m-%d %H:%M:%S");
+        let elapsed = self.end_time.map_or_else(
+            || "-".to_string(),
+            |x| format!("{:.3}s", (x - self.start_time).as_seconds_f64()),
+        );
+
+        write!(
+            f,
+            "{start_time} {0} {1} {2} {3} {elapsed} inserted {4} updated {5} skipped {6} requests {7}",
+            self.open_date,
+            self.flow_code,
+            self.job_code,
+            self.exec_status,
+            self.insert_count,
+            self.update_count,
+            self.skip_count,
+            self.request_count,
+        )?;
+        if !self.last_error.is_empty() {
+            write!(f, ": {}", self.last_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// 工作執行計數
+#[derive(Debug, Default)]
+pub struct JobCounter {
+    pub inserted: AtomicU64,
+    pub updated: AtomicU64,
+    pub skipped: AtomicU64,
+    pub requests: AtomicU64,
+}
+
+impl JobCounter {
+    /// 讀取計數
+    pub fn load(value: &AtomicU64) -> i64 {
+        i64::try_from(value.load(Ordering::Relaxed)).unwrap_or(i64::MAX)
+    }
+}
@@ -0,0 +1,123 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::{
+    future::Future,
+    sync::{atomic::Ordering, Arc},
+};
+
+use chrono::Local;
+use tracing::{event, Level};
+
+use super::{
+    dao,
+    model::{JobCounter, JobRun},
+};
+use crate::{daily_task::model::DailyTask, listen_flow, metrics, repository::Repository, Error};
+
+tokio::task_local! {
+    /// 目前工作的執行計數
+    static COUNTER: Arc<JobCounter>;
+}
+
+/// 目前工作的執行計數 (供平行子任務沿用)
+pub fn current_counter() -> Option<Arc<JobCounter>> {
+    COUNTER.try_with(Arc::clone).ok()
+}
+
+/// 於指定計數下執行
+pub async fn scope<F: Future>(counter: Option<Arc<JobCounter>>, f: F) -> F::Output {
+    match counter {
+        Some(counter) => COUNTER.scope(counter, f).await,
+        None => f.await,
+    }
+}
+
+/// 新增筆數
+pub fn count_inserted(table: &str, cnt: u64) {
+    metrics::service::add_rows(table, "insert", cnt);
+    let _ = COUNTER.try_with(|x| x.inserted.fetch_add(cnt, Ordering::Relaxed));
+}
+
+/// 修改筆數
+pub fn count_updated(table: &str, cnt: u64) {
+    metrics::service::add_rows(table, "update", cnt);
+    let _ = COUNTER.try_with(|x| x.updated.fetch_add(cnt, Ordering::Relaxed));
+}
+
+/// 略過筆數
+pub fn count_skipped(cnt: u64) {
+    let _ = COUNTER.try_with(|x| x.skipped.fetch_add(cnt, Ordering::Relaxed));
+}
+
+/// 網路請求次數
+pub fn count_request() {
+    let _ = COUNTER.try_with(|x| x.requests.fetch_add(1, Ordering::Relaxed));
+}
+
+/// 紀錄工作開始 (回傳序號，寫入失敗時不中斷工作)
+pub async fn start_job_run(repo: &Repository, flow_code: &str, task: &DailyTask) -> Option<String> {
+    let job_run = JobRun {
+        row_id: String::new(),
+        flow_code: flow_code.to_string(),
+        job_code: task.job_code.clone(),
+        open_date: task.open_date,
+        owner: listen_flow::service::current_owner().to_string(),
+        start_time: Local::now().naive_local(),
+        end_time: None,
+        exec_status: "OPEN".to_string(),
+        insert_count: 0,
+        update_count: 0,
+        skip_count: 0,
+        request_count: 0,
+        last_error: String::new(),
+    };
+
+    match dao::create(repo, job_run).await {
+        Ok(row_id) => Some(row_id),
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "job_run.start_job_run {}", &e);
+            None
+        }
+    }
+}
+
+/// 紀錄工作結束
+pub async fn end_job_run(
+    repo: &Repository,
+    row_id: &str,
+    task: &DailyTask,
+    counter: &JobCounter,
+    result: &Result<(), Error>,
+) {
+    let (exec_status, last_error) = match result {
+        Ok(()) => ("EXIT", String::new()),
+        Err(e) => ("FAIL", e.to_string()),
+    };
+    let job_run = JobRun {
+        row_id: row_id.to_string(),
+        flow_code: String::new(),
+        job_code: task.job_code.clone(),
+        open_date: task.open_date,
+        owner: String::new(),
+        start_time: Local::now().naive_local(),
+        end_time: None,
+        exec_status: exec_status.to_string(),
+        insert_count: JobCounter::load(&counter.inserted),
+        update_count: JobCounter::load(&counter.updated),
+        skip_count: JobCounter::load(&counter.skipped),
+        request_count: JobCounter::load(&counter.requests),
+        last_error,
+    };
+
+    if let Err(e) = dao::modify_by_end(repo, job_run).await {
+        event!(target: "security_api", Level::ERROR, "job_run.end_job_run {}", &e);
+    }
+}
+
+/// 列出最近的執行歷程
+pub async fn list_job_run(repo: &Repository, job_code: Option<&str>, limit: i64) {
+    let job_runs = dao::find_all_by_recent(repo, job_code, limit).await;
+    for job_run in job_runs {
+        println!("[job_run] {job_run}");
+    }
+}
@@ -1,21 +1,89 @@
 #![warn(clippy::all, clippy::pedantic)]
 
-use std::fs;
+use std::{fs, path::Path};
 
-use chrono::{Datelike, Local};
+use chrono::{Datelike, Local, NaiveDate};
+use tracing::{event, Level};
 
 mod calendar_data;
+mod config;
 mod daily_task;
 mod database_backup;
+mod dry_run;
+pub mod error;
+pub mod job;
+mod job_run;
 pub mod listen_flow;
+mod market_source;
+mod metrics;
 pub mod repository;
 mod response_data;
+mod roc_date;
+mod scheduler;
+mod security_daily_bar;
 mod security_price;
 mod security_task;
 mod security_temp;
+mod task_dependency;
 mod task_setting;
+mod throttle_event;
+mod web_api;
 
-pub fn backup_insert() -> Result<(), Box<dyn std::error::Error>> {
+pub use calendar_data::{model::HolidayDate, service::parse_holiday_schedule};
+pub use config::model::Config;
+pub use daily_task::model::DailyTask;
+pub use error::Error;
+pub use job::{
+    model::{Job, JobFuture},
+    service::JobRegistry,
+};
+pub use market_source::service::{parse_close_price, parse_daily_bar};
+pub use metrics::service::router as metrics_router;
+pub use repository::Repository;
+pub use response_data::service::parse_web_security_data;
+pub use roc_date::RocDate;
+pub use security_daily_bar::model::SecurityDailyBar;
+pub use security_temp::service::parse_table_data;
+pub use task_setting::{model::TaskSetting, service::next_run_time};
+pub use web_api::service::router;
+
+/// 任務執行選項
+#[derive(Debug, Clone, Default)]
+pub struct TaskOption {
+    /// 重新執行 (清除流程紀錄)
+    pub is_renew: bool,
+    /// 指定執行年月
+    pub open_month: Option<(i32, u32)>,
+    /// 指定市場別 (空白為全部)
+    pub market_types: Vec<String>,
+    /// 試跑 (僅列出將執行的任務與將寫入的資料)
+    pub dry_run: bool,
+}
+
+/// 讀取並驗證設定 (不載入，供檢查設定檔)
+///
+/// # Errors
+/// 設定檔無法讀取、格式錯誤或驗證失敗時
+pub fn load_config(path: Option<&Path>) -> Result<Config, Error> {
+    config::service::load(path)
+}
+
+/// 載入並驗證設定 (程式啟動時呼叫一次)
+///
+/// # Errors
+/// 設定檔無法讀取、格式錯誤或驗證失敗時
+pub fn init_config(path: Option<&Path>) -> Result<(), Error> {
+    let config = config::service::init(path)?;
+    event!(target: "security_api", Level::DEBUG, "{:?}", config);
+    Ok(())
+}
+
+/// 每日備份資料庫
+///
+/// # Errors
+/// 尚未載入設定或讀取目錄失敗時
+pub fn backup_insert() -> Result<(), Error> {
+    let config = config::service::try_get()?;
     let now_str = Local::now().format("%Y%m%d");
 
     let insert_backup = format!("security_api_insert_backup_{0}", &now_str);
@@ -28,65 +96,193 @@ pub fn backup_insert() -> Result<(), Box<dyn std::error::Error>> {
     for file in files {
         let file = file?;
 
-        if !is_insert_backup && file
-            .file_name()
-            .to_str()
-            .map_or(false, |s| s.starts_with(&insert_backup)){
-                is_insert_backup = true;
+        if !is_insert_backup
+            && file
+                .file_name()
+                .to_str()
+                .is_some_and(|s| s.starts_with(&insert_backup))
+        {
+            is_insert_backup = true;
         }
 
-        if !is_copy_backup && file
-            .file_name()
-            .to_str()
-            .map_or(false, |s| s.starts_with(&copy_backup)){
-                is_copy_backup = true;
+        if !is_copy_backup
+            && file
+                .file_name()
+                .to_str()
+                .is_some_and(|s| s.starts_with(&copy_backup))
+        {
+            is_copy_backup = true;
         }
     }
 
+    let database_name = &config.backup.database;
+
     if !is_insert_backup {
-        database_backup::DatabaseBackup.backup_insert("security_api", "security_api_insert_backup");
+        database_backup::DatabaseBackup.backup_insert(database_name, "security_api_insert_backup");
     }
 
     if !is_copy_backup {
-        database_backup::DatabaseBackup.backup_copy("security_api", "security_api_copy_backup");
+        database_backup::DatabaseBackup.backup_copy(database_name, "security_api_copy_backup");
     }
 
     Ok(())
 }
 
-pub async fn add_init_year() -> Result<(), sqlx::Error> {
-    calendar_data::service::init_calendar_data().await?;
+/// 初始化行事曆
+///
+/// # Errors
+/// 資料庫寫入失敗時
+pub async fn add_init_year(repo: &Repository) -> Result<(), Error> {
+    calendar_data::service::init_calendar_data(repo).await?;
     Ok(())
 }
 
-pub async fn add_next_year() -> Result<(), sqlx::Error> {
+/// 新增年度行事曆
+///
+/// # Errors
+/// 資料庫寫入失敗時
+pub async fn add_next_year(repo: &Repository) -> Result<(), Error> {
     let now = Local::now().date_naive();
     if 10 == now.month() && 1 == now.day() {
-        calendar_data::service::insert_calendar_data(true).await?;
-        calendar_data::service::insert_calendar_data(false).await?;
+        calendar_data::service::insert_calendar_data(repo, true).await?;
+        calendar_data::service::insert_calendar_data(repo, false).await?;
     } else {
-        calendar_data::service::insert_calendar_data(false).await?;
+        calendar_data::service::insert_calendar_data(repo, false).await?;
     }
     Ok(())
 }
 
-pub async fn add_daily_task() -> Result<(), sqlx::Error> {
-    daily_task::service::insert_task_data().await?;
+/// 匯入市場開休市日期 (未指定檔案時由證交所下載)
+///
+/// # Errors
+/// 下載、解析或資料庫寫入失敗時
+pub async fn import_holiday(
+    repo: &Repository,
+    year: i32,
+    file: Option<&Path>,
+) -> Result<(), Error> {
+    let content = match file {
+        Some(path) => fs::read_to_string(path)?,
+        None => calendar_data::service::get_holiday_schedule(year).await?,
+    };
+    let holidays = parse_holiday_schedule(&content)?;
+    calendar_data::service::import_holiday_data(repo, year, &holidays).await?;
     Ok(())
 }
 
-pub async fn run_daily_task(is_renew: bool) -> Result<(), sqlx::Error> {
-    if is_renew {
-        listen_flow::service::delete_flow_data("security").await;
-    }
-    daily_task::service::exec_daily_task().await?;
+/// 列出失敗的證券任務，指定時重新排入
+///
+/// # Errors
+/// 資料庫寫入失敗時
+pub async fn failed_task(
+    repo: &Repository,
+    security_codes: &[String],
+    requeue: bool,
+) -> Result<(), Error> {
+    security_task::service::requeue_failed_task(repo, security_codes, requeue).await?;
+    Ok(())
+}
+
+/// 分批認領並執行證券任務 (可於多台主機同時執行)
+///
+/// # Errors
+/// 資料庫寫入失敗時
+pub async fn run_worker(
+    repo: &Repository,
+    open_date: Option<NaiveDate>,
+    market_types: &[String],
+    batch_size: i64,
+) -> Result<(), Error> {
+    security_task::service::drain_task(repo, open_date, market_types, batch_size).await?;
+    Ok(())
+}
+
+/// 列出最近的工作執行歷程
+///
+/// # Errors
+/// 目前不會失敗
+pub async fn job_run(repo: &Repository, job_code: Option<&str>, limit: i64) -> Result<(), Error> {
+    job_run::service::list_job_run(repo, job_code, limit).await;
     Ok(())
 }
 
-pub async fn run_price_task(is_renew: bool) -> Result<(), Box<dyn std::error::Error>> {
-    if is_renew {
-        listen_flow::service::delete_flow_data("price").await;
+/// 新增每日任務
+///
+/// # Errors
+/// 資料庫寫入失敗時
+pub async fn add_daily_task(
+    repo: &Repository,
+    registry: &JobRegistry,
+    open_date: NaiveDate,
+    dry_run: bool,
+) -> Result<(), Error> {
+    daily_task::service::insert_task_data(repo, registry, open_date, dry_run).await?;
+    Ok(())
+}
+
+/// 執行證券任務
+///
+/// # Errors
+/// 資料庫寫入失敗或有任務失敗時
+pub async fn run_daily_task(
+    repo: &Repository,
+    registry: &JobRegistry,
+    option: &TaskOption,
+) -> Result<(), Error> {
+    if option.dry_run {
+        daily_task::service::plan_daily_task(repo, registry, "security", option).await?;
+        return Ok(());
+    }
+    if option.is_renew {
+        listen_flow::service::delete_flow_data(repo, "security").await?;
+    }
+    let summary = daily_task::service::exec_daily_task(repo, registry, option).await?;
+    daily_task::service::check_summary("security", &summary)
+}
+
+/// 執行價格任務
+///
+/// # Errors
+/// 資料庫寫入失敗或有任務失敗時
+pub async fn run_price_task(
+    repo: &Repository,
+    registry: &JobRegistry,
+    option: &TaskOption,
+) -> Result<(), Error> {
+    if option.dry_run {
+        daily_task::service::plan_daily_task(repo, registry, "price", option).await?;
+        return Ok(());
+    }
+    if option.is_renew {
+        listen_flow::service::delete_flow_data(repo, "price").await?;
     }
-    daily_task::service::exec_price_task().await?;
+    let summary = daily_task::service::exec_price_task(repo, registry, option).await?;
+    daily_task::service::check_summary("price", &summary)
+}
+
+/// 常駐排程 (依 `task_setting` 設定的間隔執行工作)
+///
+/// # Errors
+/// 資料庫讀取失敗時
+pub async fn schedule(repo: &Repository, registry: &JobRegistry) -> Result<(), Error> {
+    scheduler::service::run_scheduler(repo, registry).await?;
+    Ok(())
+}
+
+/// 啟動指標服務 (`/metrics`)
+///
+/// # Errors
+/// 無法綁定位址時
+pub async fn serve_metrics(repo: &Repository, addr: &str) -> Result<(), Error> {
+    metrics::service::serve(repo, addr).await?;
+    Ok(())
+}
+
+/// 啟動查詢服務
+///
+/// # Errors
+/// 無法綁定位址時
+pub async fn serve(repo: &Repository, addr: &str) -> Result<(), Error> {
+    web_api::service::serve(repo, addr).await?;
     Ok(())
 }
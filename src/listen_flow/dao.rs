@@ -1,16 +1,21 @@
 #![warn(clippy::all, clippy::pedantic)]
 
-use chrono::Local;
 use sqlx::{postgres::PgRow, Row};
-use tracing::{event, Level};
 
 use crate::repository::Repository;
 
 use super::model::ListenFlow;
 
-pub async fn create(data: ListenFlow) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+/// 新增流程並取得租約 (同一流程年月已存在時不新增)
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn create_by_lease(
+    repo: &Repository,
+    data: ListenFlow,
+    lease_seconds: i32,
+) -> Result<Option<String>, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -18,189 +23,239 @@ pub async fn create(data: ListenFlow) -> Result<u64, sqlx::Error> {
             flow_code
           , flow_param1
           , flow_param2
-          , flow_param3
-          , flow_param4
-          , flow_param5
           , pid
           , pstatus
+          , owner
+          , lease_until
+          , heartbeat_date
           , created_date
           , updated_date
-        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10 )
+        ) VALUES ( $1, $2, $3, $4, 'OPEN', $5, now() + make_interval(secs => $6), now(), now(), now() )
+        ON CONFLICT (flow_code, flow_param1, flow_param2) DO NOTHING
+        RETURNING row_id
     ",
     )
     .bind(data.flow_code)
     .bind(data.flow_param1)
     .bind(data.flow_param2)
-    .bind(data.flow_param3)
-    .bind(data.flow_param4)
-    .bind(data.flow_param5)
     .bind(data.pid)
-    .bind(data.pstatus)
-    .bind(Local::now())
-    .bind(Local::now())
-    .execute(&conn)
+    .bind(data.owner)
+    .bind(lease_seconds)
+    .map(|row: PgRow| row.get::<String, _>("row_id"))
+    .fetch_optional(conn)
     .await
     {
-        Ok(cnt) => Ok(cnt.rows_affected()),
+        Ok(row) => Ok(row),
         Err(e) => Err(e),
     }
 }
 
-pub async fn modify(data: ListenFlow) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+/// 接手租約過期且未結束的流程 (其他程序處理中時略過)
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn modify_by_stale(
+    repo: &Repository,
+    data: ListenFlow,
+    lease_seconds: i32,
+) -> Result<Option<String>, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
         UPDATE listen_flow 
-           SET flow_code = $1
-             , flow_param1 = $2
-             , flow_param2 = $3
-             , flow_param3 = $4
-             , flow_param4 = $5
-             , flow_param5 = $6
-             , pid = $7
-             , pstatus = $8
-             , updated_date = $9
-         WHERE row_id = $10
+           SET pid = $4
+             , pstatus = 'OPEN'
+             , owner = $5
+             , lease_until = now() + make_interval(secs => $6)
+             , heartbeat_date = now()
+             , updated_date = now()
+         WHERE row_id = (
+               SELECT row_id
+                 FROM listen_flow
+                WHERE flow_code = $1
+                  AND flow_param1 = $2
+                  AND flow_param2 = $3
+                  AND pstatus <> 'EXIT'
+                  AND lease_until < now()
+                  FOR UPDATE SKIP LOCKED
+            )
+        RETURNING row_id
     ",
     )
     .bind(data.flow_code)
     .bind(data.flow_param1)
     .bind(data.flow_param2)
-    .bind(data.flow_param3)
-    .bind(data.flow_param4)
-    .bind(data.flow_param5)
     .bind(data.pid)
-    .bind(data.pstatus)
-    .bind(Local::now())
-    .bind(data.row_id)
-    .execute(&conn)
+    .bind(data.owner)
+    .bind(lease_seconds)
+    .map(|row: PgRow| row.get::<String, _>("row_id"))
+    .fetch_optional(conn)
     .await
     {
-        Ok(cnt) => Ok(cnt.rows_affected()),
+        Ok(row) => Ok(row),
         Err(e) => Err(e),
     }
 }
 
-pub async fn remove_all(q_flow_code: &str) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+/// 取得租約過期流程的租約，不變更流程狀態 (排程執行單一工作時使用，其他程序處理中時略過)
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn modify_by_lease(
+    repo: &Repository,
+    data: ListenFlow,
+    lease_seconds: i32,
+) -> Result<Option<String>, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
-        DELETE FROM listen_flow 
-         WHERE flow_code = $1
+        UPDATE listen_flow 
+           SET pid = $4
+             , owner = $5
+             , lease_until = now() + make_interval(secs => $6)
+             , heartbeat_date = now()
+             , updated_date = now()
+         WHERE row_id = (
+               SELECT row_id
+                 FROM listen_flow
+                WHERE flow_code = $1
+                  AND flow_param1 = $2
+                  AND flow_param2 = $3
+                  AND lease_until < now()
+                  FOR UPDATE SKIP LOCKED
+            )
+        RETURNING row_id
     ",
     )
-    .bind(q_flow_code)
-    .execute(&conn)
+    .bind(data.flow_code)
+    .bind(data.flow_param1)
+    .bind(data.flow_param2)
+    .bind(data.pid)
+    .bind(data.owner)
+    .bind(lease_seconds)
+    .map(|row: PgRow| row.get::<String, _>("row_id"))
+    .fetch_optional(conn)
     .await
     {
-        Ok(cnt) => Ok(cnt.rows_affected()),
+        Ok(row) => Ok(row),
         Err(e) => Err(e),
     }
 }
 
-pub async fn find_all(data: &ListenFlow) -> Vec<ListenFlow> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
-
-    let mut select_str = r#" 
-        SELECT row_id
-             , flow_code
-             , flow_param1
-             , flow_param2
-             , flow_param3
-             , flow_param4
-             , flow_param5
-             , pid
-             , pstatus
-          FROM listen_flow
-    "#
-    .to_string();
-
-    let mut index = 0;
-    if !data.flow_code.is_empty() {
-        select_str.push_str(&where_append("flow_code", "=", &mut index));
-    }
-    if data.flow_param1.is_some() {
-        select_str.push_str(&where_append("flow_param1", "=", &mut index));
-    }
-    if data.flow_param2.is_some() {
-        select_str.push_str(&where_append("flow_param2", "=", &mut index));
-    }
-    if data.flow_param3.is_some() {
-        select_str.push_str(&where_append("flow_param3", "=", &mut index));
-    }
-    if data.flow_param4.is_some() {
-        select_str.push_str(&where_append("flow_param4", "=", &mut index));
-    }
-    if data.flow_param5.is_some() {
-        select_str.push_str(&where_append("flow_param5", "=", &mut index));
-    }
-    if data.pid > 0 {
-        select_str.push_str(&where_append("pid", "=", &mut index));
-    }
-
-    select_str.push_str("ORDER BY pid, created_date");
-
-    let mut query = sqlx::query(&select_str);
+/// 延長租約 (租約已被接手時不更新)
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn modify_by_heartbeat(
+    repo: &Repository,
+    row_id: &str,
+    owner: &str,
+    lease_seconds: i32,
+) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
-    if !data.flow_code.is_empty() {
-        query = query.bind(data.flow_code.clone());
-    }
-    if data.flow_param1.is_some() {
-        query = query.bind(data.flow_param1.clone());
-    }
-    if data.flow_param2.is_some() {
-        query = query.bind(data.flow_param2.clone());
-    }
-    if data.flow_param3.is_some() {
-        query = query.bind(data.flow_param3.clone());
-    }
-    if data.flow_param4.is_some() {
-        query = query.bind(data.flow_param4.clone());
-    }
-    if data.flow_param5.is_some() {
-        query = query.bind(data.flow_param5.clone());
-    }
-    if data.pid > 0 {
-        query = query.bind(data.pid.clone());
+    match sqlx::query(
+        r"
+        UPDATE listen_flow 
+           SET lease_until = now() + make_interval(secs => $3)
+             , heartbeat_date = now()
+             , updated_date = now()
+         WHERE row_id = $1
+           AND owner = $2
+    ",
+    )
+    .bind(row_id)
+    .bind(owner)
+    .bind(lease_seconds)
+    .execute(conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
     }
+}
+
+/// 結束流程並釋放租約
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn modify_by_exit(
+    repo: &Repository,
+    row_id: &str,
+    owner: &str,
+) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
-    match query
-        .map(|row: PgRow| ListenFlow {
-            row_id: row.get("row_id"),
-            flow_code: row.get("flow_code"),
-            flow_param1: row.get("flow_param1"),
-            flow_param2: row.get("flow_param2"),
-            flow_param3: row.get("flow_param3"),
-            flow_param4: row.get("flow_param4"),
-            flow_param5: row.get("flow_param5"),
-            pid: row.get("pid"),
-            pstatus: row.get("pstatus"),
-        })
-        .fetch_all(&conn)
-        .await
+    match sqlx::query(
+        r"
+        UPDATE listen_flow 
+           SET pstatus = 'EXIT'
+             , lease_until = now()
+             , updated_date = now()
+         WHERE row_id = $1
+           AND owner = $2
+    ",
+    )
+    .bind(row_id)
+    .bind(owner)
+    .execute(conn)
+    .await
     {
-        Ok(rows) => rows,
-        Err(e) => {
-            event!(target: "security_api", Level::ERROR, "daily_task.read_all: {}", &e);
-            Vec::new()
-        }
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
     }
 }
 
-fn where_append(field: &str, conditional: &str, index: &mut i32) -> String {
-    let plus;
-    if *index <= 0 {
-        plus = " WHERE ";
-    } else {
-        plus = " AND ";
+/// 釋放租約，不變更流程狀態
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn modify_by_release(
+    repo: &Repository,
+    row_id: &str,
+    owner: &str,
+) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        UPDATE listen_flow 
+           SET lease_until = now()
+             , updated_date = now()
+         WHERE row_id = $1
+           AND owner = $2
+    ",
+    )
+    .bind(row_id)
+    .bind(owner)
+    .execute(conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
     }
+}
 
-    *index = *index + 1;
+/// 刪除流程
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn remove_all(repo: &Repository, q_flow_code: &str) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
-    format!(" {0} {1} {2} ${3} ", plus, field, conditional, index)
+    match sqlx::query(
+        r"
+        DELETE FROM listen_flow 
+         WHERE flow_code = $1
+    ",
+    )
+    .bind(q_flow_code)
+    .execute(conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
+    }
 }
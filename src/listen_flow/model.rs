@@ -11,29 +11,31 @@ pub struct ListenFlow {
     pub flow_param5: Option<String>,
     pub pid: i32,
     pub pstatus: String,
+    pub owner: String,
 }
 
 impl std::fmt::Display for ListenFlow {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         let flow_code = self.flow_code.clone();
-        let flow_param1 = self.flow_param1.clone().unwrap_or(String::from(""));
-        let flow_param2 = self.flow_param2.clone().unwrap_or(String::from(""));
-        let flow_param3 = self.flow_param3.clone().unwrap_or(String::from(""));
-        let flow_param4 = self.flow_param4.clone().unwrap_or(String::from(""));
-        let flow_param5 = self.flow_param5.clone().unwrap_or(String::from(""));
+        let flow_param1 = self.flow_param1.clone().unwrap_or_default();
+        let flow_param2 = self.flow_param2.clone().unwrap_or_default();
+        let flow_param3 = self.flow_param3.clone().unwrap_or_default();
+        let flow_param4 = self.flow_param4.clone().unwrap_or_default();
+        let flow_param5 = self.flow_param5.clone().unwrap_or_default();
         let pid = self.pid;
+        let owner = self.owner.clone();
 
         write!(
             f,
-            r#"{0}, 
-            flow_param1: {1}, 
-            flow_param1: {2}, 
-            flow_param1: {3},
-            flow_param1: {4},
-            flow_param1: {5},
-            pid: {6}
-            "#,
-            flow_code, flow_param1, flow_param2, flow_param3, flow_param4, flow_param5, pid
+            r"{flow_code}, 
+            flow_param1: {flow_param1}, 
+            flow_param1: {flow_param2}, 
+            flow_param1: {flow_param3},
+            flow_param1: {flow_param4},
+            flow_param1: {flow_param5},
+            pid: {pid},
+            owner: {owner}
+            "
         )
     }
 }
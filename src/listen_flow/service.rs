@@ -1,12 +1,33 @@
 #![warn(clippy::all, clippy::pedantic)]
 
+use std::{env, fs, process, sync::OnceLock};
+
+use chrono::Local;
+use tokio::task::JoinHandle;
+use tracing::{event, Level};
+
 use super::{dao, model::ListenFlow};
+use crate::{config, repository::Repository, Error};
+
+/// 刪除流程資料
+///
+/// # Errors
+/// 資料庫寫入失敗時
+pub async fn delete_flow_data(repo: &Repository, flow_code: &str) -> Result<(), Error> {
+    dao::remove_all(repo, flow_code).await?;
+    Ok(())
+}
 
-pub async fn read_flow_data(
+/// 認領流程 (新增或接手租約過期的流程，回傳流程序號，其他程序處理中時回傳 None)
+///
+/// # Errors
+/// 資料庫寫入失敗時
+pub async fn claim_flow_data(
+    repo: &Repository,
     flow_code: &str,
     flow_param1: &str,
     flow_param2: &str,
-) -> Vec<ListenFlow> {
+) -> Result<Option<String>, Error> {
     let listen_flow = ListenFlow {
         row_id: String::new(),
         flow_code: flow_code.to_string(),
@@ -15,18 +36,33 @@ pub async fn read_flow_data(
         flow_param3: None,
         flow_param4: None,
         flow_param5: None,
-        pid: 0,
+        pid: i32::try_from(process::id()).unwrap_or_default(),
         pstatus: String::new(),
+        owner: current_owner().to_string(),
     };
 
-    dao::find_all(&listen_flow).await
-}
+    let lease_secs = config::service::get().flow.lease_secs;
+    if let Some(row_id) = dao::create_by_lease(repo, listen_flow.clone(), lease_secs).await? {
+        return Ok(Some(row_id));
+    }
 
-pub async fn delete_flow_data(flow_code: &str) {
-    dao::remove_all(flow_code).await.unwrap();
+    let row_id = dao::modify_by_stale(repo, listen_flow, lease_secs).await?;
+    if row_id.is_some() {
+        event!(target: "security_api", Level::WARN, "listen_flow.{} {}/{} reclaimed stale lease", flow_code, flow_param1, flow_param2);
+    }
+    Ok(row_id)
 }
 
-pub async fn insert_flow_data2(pid: i32, flow_code: &str, flow_param1: &str, flow_param2: &str) {
+/// 取得流程年月的租約，不結束流程 (排程執行單一工作時使用，其他程序處理中時回傳 None)
+///
+/// # Errors
+/// 資料庫寫入失敗時
+pub async fn lease_flow_data(
+    repo: &Repository,
+    flow_code: &str,
+    flow_param1: &str,
+    flow_param2: &str,
+) -> Result<Option<String>, Error> {
     let listen_flow = ListenFlow {
         row_id: String::new(),
         flow_code: flow_code.to_string(),
@@ -35,46 +71,78 @@ pub async fn insert_flow_data2(pid: i32, flow_code: &str, flow_param1: &str, flo
         flow_param3: None,
         flow_param4: None,
         flow_param5: None,
-        pid,
+        pid: i32::try_from(process::id()).unwrap_or_default(),
         pstatus: String::new(),
+        owner: current_owner().to_string(),
     };
 
-    let flows = dao::find_all(&listen_flow).await;
-    if flows.len() <= 0 {
-        let new_listen_flow = ListenFlow {
-            row_id: String::new(),
-            flow_code: flow_code.to_string(),
-            flow_param1: Some(flow_param1.to_string()),
-            flow_param2: Some(flow_param2.to_string()),
-            flow_param3: None,
-            flow_param4: None,
-            flow_param5: None,
-            pid,
-            pstatus: "WAIT".to_string(),
-        };
+    let lease_secs = config::service::get().flow.lease_secs;
+    if let Some(row_id) = dao::create_by_lease(repo, listen_flow.clone(), lease_secs).await? {
+        return Ok(Some(row_id));
+    }
+    Ok(dao::modify_by_lease(repo, listen_flow, lease_secs).await?)
+}
 
-        dao::create(new_listen_flow).await.unwrap();
+/// 釋放租約 (流程狀態不變，其他程序可再認領)
+///
+/// # Errors
+/// 資料庫寫入失敗時
+pub async fn release_flow_data(repo: &Repository, row_id: &str) -> Result<(), Error> {
+    if dao::modify_by_release(repo, row_id, current_owner()).await? == 0 {
+        event!(target: "security_api", Level::WARN, "listen_flow.release_flow_data {} lease lost", row_id);
     }
+    Ok(())
 }
 
-pub async fn modify_flow_data2(pid: i32, flow_code: &str, flow_param1: &str, flow_param2: &str) {
-    let listen_flow = ListenFlow {
-        row_id: String::new(),
-        flow_code: flow_code.to_string(),
-        flow_param1: Some(flow_param1.to_string()),
-        flow_param2: Some(flow_param2.to_string()),
-        flow_param3: None,
-        flow_param4: None,
-        flow_param5: None,
-        pid,
-        pstatus: String::new(),
-    };
+/// 定期延長租約 (流程結束時中止)
+#[must_use]
+pub fn start_heartbeat(repo: &Repository, row_id: &str) -> JoinHandle<()> {
+    let repo = repo.clone();
+    let row_id = row_id.to_string();
 
-    let flows = dao::find_all(&listen_flow).await;
-    for flow in flows {
-        let mut new_flow = flow;
-        new_flow.pstatus = "EXIT".to_string();
+    tokio::spawn(async move {
+        let flow = &config::service::get().flow;
+        let mut interval = tokio::time::interval(flow.heartbeat());
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match dao::modify_by_heartbeat(&repo, &row_id, current_owner(), flow.lease_secs).await {
+                Ok(0) => {
+                    event!(target: "security_api", Level::WARN, "listen_flow.heartbeat {} lease lost", &row_id);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    event!(target: "security_api", Level::ERROR, "listen_flow.heartbeat {}", &e);
+                }
+            }
+        }
+    })
+}
 
-        dao::modify(new_flow).await.unwrap();
+/// 結束流程資料
+///
+/// # Errors
+/// 資料庫寫入失敗時
+pub async fn end_flow_data(repo: &Repository, row_id: &str) -> Result<(), Error> {
+    if dao::modify_by_exit(repo, row_id, current_owner()).await? == 0 {
+        event!(target: "security_api", Level::WARN, "listen_flow.end_flow_data {} lease lost", row_id);
     }
+    Ok(())
+}
+
+/// 執行者 (主機:線程ID:啟動時間，避免重開機後線程ID重複)
+#[must_use]
+pub fn current_owner() -> &'static str {
+    static OWNER: OnceLock<String> = OnceLock::new();
+
+    OWNER.get_or_init(|| {
+        let host = env::var("HOSTNAME")
+            .ok()
+            .or_else(|| fs::read_to_string("/etc/hostname").ok())
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .unwrap_or_else(|| "localhost".to_string());
+        let started = Local::now().format("%Y%m%d%H%M%S%3f");
+        format!("{host}:{}:{started}", process::id())
+    })
 }
@@ -1,127 +1,536 @@
 #![warn(clippy::all, clippy::pedantic)]
 
-use std::env;
+use std::{path::PathBuf, process::ExitCode};
 
+use chrono::{Datelike, Local, NaiveDate};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use security_api::{Error, JobRegistry, Repository, TaskOption};
 use tracing::{event, Level};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    fmt::MakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer, Registry,
+};
+
+/// 證券資料批次
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// 日誌格式 (主控台)
+    #[arg(long, value_enum, global = true, default_value_t = LogFormat::Json)]
+    log_format: LogFormat,
+
+    /// 日誌檔格式
+    #[arg(long, value_enum, global = true, default_value_t = LogFormat::Json)]
+    log_file_format: LogFormat,
+
+    /// 日誌目錄
+    #[arg(long, global = true, default_value = "logs")]
+    log_dir: PathBuf,
+
+    /// 日誌檔輪替週期
+    #[arg(long, value_enum, global = true, default_value_t = LogRotation::Hourly)]
+    log_rotation: LogRotation,
+
+    /// 日誌檔保留數量 (未指定時全部保留)
+    #[arg(long, global = true)]
+    log_max_files: Option<usize>,
+
+    /// 設定檔 (未指定時讀取 `SECURITY_API_CONFIG` 或 `security_api.toml`)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// 指標服務位址 (啟用時於執行期間提供 `/metrics`)
+    #[arg(long, global = true)]
+    metrics_addr: Option<String>,
+
+    /// 未指定時依序執行 `add_daily_task`、`rerun_daily_task`、`rerun_price_task`
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+#[command(rename_all = "snake_case")]
+enum Command {
+    /// 初始化行事曆
+    AddInitYear,
+    /// 新增年度行事曆 (10/1 同時新增下一年度)
+    AddNextYear,
+    /// 匯入市場開休市日期
+    ImportHoliday(HolidayArgs),
+    /// 新增每日任務
+    AddDailyTask(DateArgs),
+    /// 執行證券任務
+    RunDailyTask(DailyArgs),
+    /// 執行價格任務
+    RunPriceTask(PriceArgs),
+    /// 清除流程紀錄後執行證券任務
+    RerunDailyTask(DailyArgs),
+    /// 清除流程紀錄後執行價格任務
+    RerunPriceTask(PriceArgs),
+    /// 新增每日任務後執行證券任務與價格任務
+    DailyTask(AllArgs),
+    /// 列出失敗的證券任務
+    FailedTask(FailedArgs),
+    /// 列出最近的工作執行歷程
+    JobRun(JobRunArgs),
+    /// 分批認領並執行證券任務 (可同時執行多個)
+    Worker(WorkerArgs),
+    /// 常駐排程 (取代外部 cron)
+    Schedule,
+    /// 啟動查詢服務
+    Serve {
+        /// 綁定位址
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+}
+
+#[derive(Debug, Args)]
+struct DateArgs {
+    /// 任務日期 (YYYY-MM-DD)，預設今日
+    #[arg(long)]
+    date: Option<NaiveDate>,
+
+    /// 僅列出將新增的任務
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+struct HolidayArgs {
+    /// 年度 (YYYY)，預設今年
+    #[arg(long)]
+    year: Option<i32>,
+
+    /// 開休市日期檔 (TWSE JSON)，未指定時由證交所下載
+    #[arg(long)]
+    file: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct DailyArgs {
+    /// 指定執行年月 (YYYY-MM)
+    #[arg(long, value_parser = parse_month)]
+    month: Option<(i32, u32)>,
+
+    /// 指定市場別，可重複指定
+    #[arg(long, value_enum)]
+    market: Vec<Market>,
+
+    /// 試跑 (僅列出將執行的任務與將寫入的資料)
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+struct PriceArgs {
+    /// 指定執行年月 (YYYY-MM)
+    #[arg(long, value_parser = parse_month)]
+    month: Option<(i32, u32)>,
+
+    /// 試跑 (僅列出將執行的任務與將寫入的資料)
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+struct FailedArgs {
+    /// 指定證券代碼，可重複指定
+    #[arg(long)]
+    code: Vec<String>,
+
+    /// 重新排入失敗任務
+    #[arg(long)]
+    requeue: bool,
+}
+
+#[derive(Debug, Args)]
+struct JobRunArgs {
+    /// 指定工作代碼
+    #[arg(long)]
+    job: Option<String>,
+
+    /// 列出筆數
+    #[arg(long, default_value_t = 20)]
+    limit: i64,
+}
+
+#[derive(Debug, Args)]
+struct WorkerArgs {
+    /// 任務日期 (YYYY-MM-DD)，預設全部
+    #[arg(long)]
+    date: Option<NaiveDate>,
+
+    /// 指定市場別，可重複指定
+    #[arg(long, value_enum)]
+    market: Vec<Market>,
+
+    /// 每批認領的任務數
+    #[arg(long, default_value_t = 20)]
+    batch: i64,
+}
+
+#[derive(Debug, Args)]
+struct AllArgs {
+    /// 任務日期 (YYYY-MM-DD)，預設今日
+    #[arg(long)]
+    date: Option<NaiveDate>,
+
+    /// 指定市場別，可重複指定
+    #[arg(long, value_enum)]
+    market: Vec<Market>,
+
+    /// 試跑 (僅列出將執行的任務與將寫入的資料)
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum LogFormat {
+    Json,
+    Pretty,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum LogRotation {
+    Minutely,
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl LogRotation {
+    fn rotation(self) -> Rotation {
+        match self {
+            LogRotation::Minutely => Rotation::MINUTELY,
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Market {
+    /// 上市
+    Twse,
+    /// 上櫃
+    Tpex,
+    /// 興櫃
+    Emerging,
+}
+
+impl Market {
+    fn market_type(self) -> String {
+        match self {
+            Market::Twse => "上市",
+            Market::Tpex => "上櫃",
+            Market::Emerging => "興櫃",
+        }
+        .to_string()
+    }
+}
+
+impl DailyArgs {
+    fn task_option(&self, is_renew: bool) -> TaskOption {
+        TaskOption {
+            is_renew,
+            open_month: self.month,
+            market_types: self.market.iter().map(|x| x.market_type()).collect(),
+            dry_run: self.dry_run,
+        }
+    }
+}
+
+impl AllArgs {
+    fn task_option(&self) -> TaskOption {
+        TaskOption {
+            is_renew: false,
+            open_month: None,
+            market_types: self.market.iter().map(|x| x.market_type()).collect(),
+            dry_run: self.dry_run,
+        }
+    }
+}
+
+impl WorkerArgs {
+    fn market_types(&self) -> Vec<String> {
+        self.market.iter().map(|x| x.market_type()).collect()
+    }
+}
+
+impl PriceArgs {
+    fn task_option(&self, is_renew: bool) -> TaskOption {
+        TaskOption {
+            is_renew,
+            open_month: self.month,
+            market_types: Vec::new(),
+            dry_run: self.dry_run,
+        }
+    }
+}
+
+/// 解析年月 (YYYY-MM)
+fn parse_month(value: &str) -> Result<(i32, u32), String> {
+    NaiveDate::parse_from_str(&format!("{value}-01"), "%Y-%m-%d")
+        .map(|date| (date.year(), date.month()))
+        .map_err(|_| format!("invalid month `{value}`, expected YYYY-MM"))
+}
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    // 保留至程式結束，結束時寫出剩餘日誌
+    let _guards = match init_logging(&cli) {
+        Ok(guards) => guards,
+        Err(e) => {
+            eprintln!("init_logging Error {e}");
+            return ExitCode::from(e.exit_code());
+        }
+    };
+
+    if let Err(e) = security_api::init_config(cli.config.as_deref()) {
+        event!(target: "security_api", Level::ERROR, "init_config Error {}", &e);
+        return ExitCode::from(e.exit_code());
+    }
+
+    let repo = match Repository::new().await {
+        Ok(repo) => repo,
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "repository Error {}", &e);
+            return ExitCode::from(e.exit_code());
+        }
+    };
+
+    if let Some(addr) = cli.metrics_addr {
+        let repo = repo.clone();
+        tokio::spawn(async move {
+            if let Err(e) = security_api::serve_metrics(&repo, &addr).await {
+                event!(target: "security_api", Level::ERROR, "serve_metrics Error {}", &e);
+            }
+        });
+    }
+
+    match run(&repo, cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => ExitCode::from(e.exit_code()),
+    }
+}
+
+async fn run(repo: &Repository, command: Option<Command>) -> Result<(), Error> {
+    let today = Local::now().date_naive();
+    let registry = JobRegistry::new();
+
+    match command {
+        Some(Command::AddInitYear) => {
+            backup_insert(false)?;
+            run_step("add_init_year", security_api::add_init_year(repo)).await
+        }
+        Some(Command::AddNextYear) => {
+            backup_insert(false)?;
+            run_step("add_next_year", security_api::add_next_year(repo)).await
+        }
+        Some(Command::ImportHoliday(args)) => {
+            backup_insert(false)?;
+            let year = args.year.unwrap_or(today.year());
+            run_step(
+                "import_holiday",
+                security_api::import_holiday(repo, year, args.file.as_deref()),
+            )
+            .await
+        }
+        Some(Command::AddDailyTask(args)) => {
+            backup_insert(args.dry_run)?;
+            let open_date = args.date.unwrap_or(today);
+            run_step(
+                "add_daily_task",
+                security_api::add_daily_task(repo, &registry, open_date, args.dry_run),
+            )
+            .await
+        }
+        Some(Command::RunDailyTask(args)) => {
+            run_daily(repo, &registry, &args.task_option(false)).await
+        }
+        Some(Command::RerunDailyTask(args)) => {
+            run_daily(repo, &registry, &args.task_option(true)).await
+        }
+        Some(Command::RunPriceTask(args)) => {
+            run_price(repo, &registry, &args.task_option(false)).await
+        }
+        Some(Command::RerunPriceTask(args)) => {
+            run_price(repo, &registry, &args.task_option(true)).await
+        }
+        Some(Command::DailyTask(args)) => {
+            let option = args.task_option();
+            backup_insert(option.dry_run)?;
+            run_daily_steps(repo, &registry, args.date.unwrap_or(today), &option).await
+        }
+        Some(Command::FailedTask(args)) => {
+            run_step(
+                "failed_task",
+                security_api::failed_task(repo, &args.code, args.requeue),
+            )
+            .await
+        }
+        Some(Command::JobRun(args)) => {
+            run_step(
+                "job_run",
+                security_api::job_run(repo, args.job.as_deref(), args.limit),
+            )
+            .await
+        }
+        Some(Command::Worker(args)) => {
+            let market_types = args.market_types();
+            run_step(
+                "worker",
+                security_api::run_worker(repo, args.date, &market_types, args.batch),
+            )
+            .await
+        }
+        Some(Command::Schedule) => {
+            run_step("schedule", security_api::schedule(repo, &registry)).await
+        }
+        Some(Command::Serve { addr }) => run_step("serve", security_api::serve(repo, &addr)).await,
+        None => {
+            let option = TaskOption {
+                is_renew: true,
+                ..TaskOption::default()
+            };
+            backup_insert(false)?;
+            run_daily_steps(repo, &registry, today, &option).await
+        }
+    }
+}
+
+/// 初始化主控台與日誌檔 (回傳的 guard 需保留至程式結束)
+fn init_logging(cli: &Cli) -> Result<Vec<WorkerGuard>, Error> {
     let log_filter =
         std::env::var("RUST_LOG").unwrap_or_else(|_| "security_api=info,sqlx=error".to_owned());
 
     // console log
-    let (console_non_blocking, _guard) = tracing_appender::non_blocking(std::io::stdout());
+    let (console_non_blocking, console_guard) = tracing_appender::non_blocking(std::io::stdout());
     // log file
-    let file_appender = tracing_appender::rolling::hourly("logs", "security_api.log");
-    let (_file_non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
+    let mut file_builder = RollingFileAppender::builder()
+        .rotation(cli.log_rotation.rotation())
+        .filename_prefix("security_api.log");
+    if let Some(max_files) = cli.log_max_files {
+        file_builder = file_builder.max_log_files(max_files);
+    }
+    let file_appender = file_builder
+        .build(&cli.log_dir)
+        .map_err(|e| Error::Io(std::io::Error::other(e)))?;
+    let (file_non_blocking, file_guard) = tracing_appender::non_blocking(file_appender);
 
-    tracing_subscriber::fmt()
-        .json()
-        .with_env_filter(log_filter)
-        .with_writer(console_non_blocking)
+    tracing_subscriber::registry()
+        .with(vec![
+            fmt_layer(cli.log_format, console_non_blocking, true, &log_filter),
+            fmt_layer(cli.log_file_format, file_non_blocking, false, &log_filter),
+        ])
         .init();
 
-    security_api::backup_insert().unwrap();
-
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        let action_code = args[1].as_str();
-        match action_code {
-            "add_init_year" => match security_api::add_init_year().await {
-                Ok(_) => event!(target: "security_api", Level::INFO, "add_init_year Done"),
-                Err(e) => {
-                    event!(target: "security_api", Level::ERROR, "add_init_year {}", &e);
-                    panic!("add_init_year Error {}", &e)
-                }
-            },
-            "add_next_year" => match security_api::add_next_year().await {
-                Ok(_) => event!(target: "security_api", Level::INFO, "add_next_year Done"),
-                Err(e) => {
-                    event!(target: "security_api",  Level::ERROR,"add_next_year {}", &e);
-                    panic!("add_next_year Error {}", &e)
-                }
-            },
-            "add_daily_task" => match security_api::add_daily_task().await {
-                Ok(_) => event!(target: "security_api", Level::INFO, "add_daily_task Done"),
-                Err(e) => {
-                    event!(target: "security_api",  Level::ERROR,"add_daily_task {}", &e);
-                    panic!("add_daily_task Error {}", &e)
-                }
-            },
-            "run_daily_task" => match security_api::run_daily_task(false).await {
-                Ok(_) => event!(target: "security_api", Level::INFO, "run_daily_task Done"),
-                Err(e) => {
-                    event!(target: "security_api", Level::ERROR, "run_daily_task {}", &e);
-                    panic!("run_daily_task Error {}", &e)
-                }
-            },
-            "run_price_task" => match security_api::run_price_task(false).await {
-                Ok(_) => event!(target: "security_api", Level::INFO, "run_price_task Done"),
-                Err(e) => {
-                    event!(target: "security_api", Level::ERROR,"run_price_task {}", &e);
-                    panic!("run_price_task Error {}", &e)
-                }
-            },
-            "daily_task" => {
-                match security_api::add_daily_task().await {
-                    Ok(_) => event!(target: "security_api",Level::INFO,  "add_daily_task Done"),
-                    Err(e) => {
-                        event!(target: "security_api", Level::ERROR, "add_daily_task {}", &e);
-                        panic!("add_daily_task Error {}", &e)
-                    }
-                };
-                match security_api::run_daily_task(false).await {
-                    Ok(_) => event!(target: "security_api", Level::INFO, "run_daily_task Done"),
-                    Err(e) => {
-                        event!(target: "security_api", Level::ERROR,"run_daily_task {}", &e);
-                        panic!("run_daily_task Error {}", &e)
-                    }
-                };
-                match security_api::run_price_task(false).await {
-                    Ok(_) => event!(target: "security_api", Level::INFO, "run_price_task Done"),
-                    Err(e) => {
-                        event!(target: "security_api", Level::ERROR, "run_price_task {}", &e);
-                        panic!("run_price_task Error {}", &e)
-                    }
-                };
-            }
-            "rerun_daily_task" => match security_api::run_daily_task(true).await {
-                Ok(_) => event!(target: "security_api", Level::INFO, "run_daily_task Done"),
-                Err(e) => {
-                    event!(target: "security_api", Level::ERROR, "run_daily_task {}", &e);
-                    panic!("run_daily_task Error {}", &e)
-                }
-            },
-            "rerun_price_task" => match security_api::run_price_task(true).await {
-                Ok(_) => event!(target: "security_api", Level::INFO, "run_price_task Done"),
-                Err(e) => {
-                    event!(target: "security_api", Level::ERROR, "run_price_task {}", &e);
-                    panic!("run_price_task Error {}", &e)
-                }
-            },
-            _ => event!(target: "security_api", Level::INFO, "{:?}", args[1]),
-        }
-    } else {
-        match security_api::add_daily_task().await {
-            Ok(_) => event!(target: "security_api", Level::INFO, "add_daily_task Done"),
-            Err(e) => {
-                event!(target: "security_api", Level::ERROR, "add_daily_task {}", &e);
-                panic!("add_daily_task Error {}", &e)
-            }
-        };
-        match security_api::run_daily_task(true).await {
-            Ok(_) => event!(target: "security_api", Level::INFO, "run_daily_task Done"),
-            Err(e) => {
-                event!(target: "security_api", Level::ERROR, "run_daily_task {}", &e);
-                panic!("run_daily_task Error {}", &e)
-            }
-        };
-        match security_api::run_price_task(true).await {
-            Ok(_) => event!(target: "security_api", Level::INFO, "run_price_task Done"),
-            Err(e) => {
-                event!(target: "security_api", Level::ERROR, "run_price_task {}", &e);
-                panic!("run_price_task Error {}", &e)
-            }
-        };
+    Ok(vec![console_guard, file_guard])
+}
+
+/// 依格式建立日誌輸出
+fn fmt_layer<W>(
+    format: LogFormat,
+    writer: W,
+    ansi: bool,
+    log_filter: &str,
+) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi);
+
+    match format {
+        LogFormat::Json => layer.json().with_filter(EnvFilter::new(log_filter)).boxed(),
+        LogFormat::Pretty => layer
+            .pretty()
+            .with_filter(EnvFilter::new(log_filter))
+            .boxed(),
+    }
+}
+
+/// 備份資料庫 (試跑時略過)
+fn backup_insert(dry_run: bool) -> Result<(), Error> {
+    if !dry_run {
+        security_api::backup_insert().inspect_err(|e| {
+            event!(target: "security_api", Level::ERROR, "backup_insert Error {}", e);
+        })?;
+    }
+    Ok(())
+}
+
+/// 執行證券任務
+async fn run_daily(
+    repo: &Repository,
+    registry: &JobRegistry,
+    option: &TaskOption,
+) -> Result<(), Error> {
+    backup_insert(option.dry_run)?;
+    run_step(
+        "run_daily_task",
+        security_api::run_daily_task(repo, registry, option),
+    )
+    .await
+}
+
+/// 執行價格任務
+async fn run_price(
+    repo: &Repository,
+    registry: &JobRegistry,
+    option: &TaskOption,
+) -> Result<(), Error> {
+    backup_insert(option.dry_run)?;
+    run_step(
+        "run_price_task",
+        security_api::run_price_task(repo, registry, option),
+    )
+    .await
+}
+
+async fn run_daily_steps(
+    repo: &Repository,
+    registry: &JobRegistry,
+    open_date: NaiveDate,
+    option: &TaskOption,
+) -> Result<(), Error> {
+    run_step(
+        "add_daily_task",
+        security_api::add_daily_task(repo, registry, open_date, option.dry_run),
+    )
+    .await?;
+    run_step(
+        "run_daily_task",
+        security_api::run_daily_task(repo, registry, option),
+    )
+    .await?;
+    run_step(
+        "run_price_task",
+        security_api::run_price_task(repo, registry, option),
+    )
+    .await
+}
+
+async fn run_step(
+    action_code: &str,
+    step: impl std::future::Future<Output = Result<(), Error>>,
+) -> Result<(), Error> {
+    match step.await {
+        Ok(()) => {
+            event!(target: "security_api", Level::INFO, "{} Done", action_code);
+            Ok(())
+        }
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "{} Error {}", action_code, &e);
+            Err(e)
+        }
     }
 }
@@ -0,0 +1,110 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::{sync::Arc, time::Duration};
+
+use rand::{rng, Rng};
+use tokio::{
+    sync::{Mutex, OwnedSemaphorePermit, Semaphore},
+    time::{sleep, Instant},
+};
+use tracing::{event, Level};
+
+use super::model::RateLimit;
+
+/// 額度桶
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refill_at: Instant,
+    paused_until: Option<Instant>,
+    backoff: Duration,
+}
+
+/// 單一主機的流量控制 (token bucket + 同時查詢數 + 退避)
+#[derive(Debug)]
+pub struct MarketLimiter {
+    host: &'static str,
+    rate_limit: RateLimit,
+    bucket: Mutex<Bucket>,
+    semaphore: Arc<Semaphore>,
+}
+
+impl MarketLimiter {
+    pub fn new(host: &'static str, rate_limit: RateLimit) -> Self {
+        MarketLimiter {
+            host,
+            rate_limit,
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(rate_limit.burst),
+                refill_at: Instant::now(),
+                paused_until: None,
+                backoff: Duration::ZERO,
+            }),
+            semaphore: Arc::new(Semaphore::new(rate_limit.concurrency.max(1))),
+        }
+    }
+
+    /// 取得同時查詢名額
+    pub async fn permit(&self) -> OwnedSemaphorePermit {
+        Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .await
+            .expect("market limiter semaphore closed")
+    }
+
+    /// 等待查詢額度
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+                self.refill(&mut bucket, now);
+
+                match bucket.paused_until {
+                    Some(until) if until > now => until - now,
+                    _ if bucket.tokens >= 1.0 => {
+                        bucket.tokens -= 1.0;
+                        return;
+                    }
+                    _ => self.rate_limit.interval.mul_f64(1.0 - bucket.tokens),
+                }
+            };
+            sleep(wait).await;
+        }
+    }
+
+    /// 被限制流量時暫停整個主機佇列 (每次加倍至上限，另加隨機延遲)
+    pub async fn backoff(&self) -> Duration {
+        let mut bucket = self.bucket.lock().await;
+        bucket.backoff = (bucket.backoff * 2)
+            .max(self.rate_limit.backoff_min)
+            .min(self.rate_limit.backoff_max);
+
+        let jitter_millis = u64::try_from(bucket.backoff.as_millis() / 2).unwrap_or(u64::MAX);
+        let wait = bucket.backoff + Duration::from_millis(rng().random_range(0..=jitter_millis));
+
+        let now = Instant::now();
+        bucket.tokens = 0.0;
+        bucket.paused_until = Some(
+            bucket
+                .paused_until
+                .map_or(now + wait, |x| x.max(now + wait)),
+        );
+
+        event!(target: "security_api", Level::WARN, "{} backoff {:?}", self.host, wait);
+        wait
+    }
+
+    /// 查詢成功後重設退避
+    pub async fn reset(&self) {
+        self.bucket.lock().await.backoff = Duration::ZERO;
+    }
+
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        let elapsed = now.duration_since(bucket.refill_at);
+        let tokens = elapsed.as_secs_f64() / self.rate_limit.interval.as_secs_f64();
+
+        bucket.tokens = (bucket.tokens + tokens).min(f64::from(self.rate_limit.burst));
+        bucket.refill_at = now;
+    }
+}
@@ -0,0 +1,5 @@
+pub mod limiter;
+pub mod model;
+pub mod service;
+pub mod tpex;
+pub mod twse;
@@ -0,0 +1,65 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::time::Duration;
+
+use reqwest::{Client, RequestBuilder};
+
+use crate::{
+    config::model::SourceConfig, response_data::model::FetchOutcome,
+    security_task::model::SecurityTask, Error,
+};
+
+/// 市場資料來源
+pub trait MarketSource: Send + Sync {
+    /// 市場別
+    fn market_type(&self) -> &'static str;
+
+    /// 來源設定
+    fn config(&self) -> &'static SourceConfig;
+
+    /// 來源主機
+    fn host(&self) -> &'static str {
+        &self.config().host
+    }
+
+    /// 建立查詢請求
+    fn build_request(&self, client: &Client, task: &SecurityTask) -> RequestBuilder;
+
+    /// 解析回應內容
+    ///
+    /// # Errors
+    /// 回應格式錯誤時
+    fn parse(&self, body: &str, task: &SecurityTask) -> Result<FetchOutcome, Error>;
+
+    /// 查詢流量限制 (同主機共用)
+    fn rate_limit(&self) -> RateLimit {
+        RateLimit::from(self.config())
+    }
+}
+
+/// 查詢流量限制
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    /// 每個額度的補充間隔
+    pub interval: Duration,
+    /// 最大累積額度
+    pub burst: u32,
+    /// 同時查詢數
+    pub concurrency: usize,
+    /// 被限制流量時的最短暫停
+    pub backoff_min: Duration,
+    /// 被限制流量時的最長暫停
+    pub backoff_max: Duration,
+}
+
+impl From<&SourceConfig> for RateLimit {
+    fn from(config: &SourceConfig) -> Self {
+        RateLimit {
+            interval: Duration::from_secs(config.interval_secs),
+            burst: config.burst,
+            concurrency: config.concurrency,
+            backoff_min: Duration::from_secs(config.backoff_min_secs),
+            backoff_max: Duration::from_secs(config.backoff_max_secs),
+        }
+    }
+}
@@ -0,0 +1,275 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::{
+    cmp::max,
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, LazyLock},
+    time::Instant,
+};
+
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDate;
+use reqwest::Client;
+use serde_json::Value;
+use tracing::{event, Level};
+
+use crate::{
+    job_run, metrics,
+    response_data::{
+        self,
+        model::{FetchOutcome, MonthlyPrice},
+    },
+    roc_date::RocDate,
+    security_daily_bar::model::SecurityDailyBar,
+    security_task::model::SecurityTask,
+    Error,
+};
+
+use super::{
+    limiter::MarketLimiter,
+    model::MarketSource,
+    tpex::{TpexEmerging, TpexListed},
+    twse::Twse,
+};
+
+/// 已註冊的市場資料來源
+static SOURCES: [&dyn MarketSource; 3] = [&Twse, &TpexListed, &TpexEmerging];
+
+/// 共用連線 (重用連線池，逾時依來源設定)
+static CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
+
+/// 取得共用連線 (逾時於每次請求設定)
+pub fn client() -> &'static Client {
+    &CLIENT
+}
+
+/// 依市場別取得資料來源
+pub fn find_source(market_type: &str) -> Option<&'static dyn MarketSource> {
+    SOURCES
+        .iter()
+        .find(|x| x.market_type() == market_type)
+        .copied()
+}
+
+/// 被限制流量時的回應訊息
+const THROTTLE_MESSAGES: [&str; 5] = [
+    "過於頻繁",
+    "請稍後再試",
+    "暫停服務",
+    "Access Denied",
+    "Too Many Requests",
+];
+
+/// 依主機建立流量控制
+pub fn build_limiters() -> HashMap<&'static str, Arc<MarketLimiter>> {
+    let mut limiters = HashMap::new();
+    for source in SOURCES {
+        limiters
+            .entry(source.host())
+            .or_insert_with(|| Arc::new(MarketLimiter::new(source.host(), source.rate_limit())));
+    }
+    limiters
+}
+
+/// 取得證券價格
+pub async fn fetch(source: &dyn MarketSource, task: &SecurityTask) -> Result<FetchOutcome, Error> {
+    run_task_log(task);
+
+    job_run::service::count_request();
+    let started = Instant::now();
+    let res = source
+        .build_request(&CLIENT, task)
+        .timeout(source.config().timeout())
+        .send()
+        .await;
+    metrics::service::observe_request(&task.market_type, started.elapsed());
+    let res = res?;
+    event!(target: "security_api", Level::DEBUG, "{:?}", &res.url().to_string());
+
+    let url = res.url().to_string();
+    let body = response_data::service::check_status(res)?.text().await?;
+    if let Some(reason) = check_throttle(&body) {
+        return Err(Error::RateLimited(format!("{reason} {url}")));
+    }
+    let outcome = source.parse(&body, task)?;
+    event!(target: "security_api", Level::DEBUG, "{:?}", &outcome);
+
+    Ok(outcome)
+}
+
+/// 解析來源回應的當月收盤價 (民國日期, 收盤價)，查無資料或暫停交易時為空
+///
+/// # Errors
+/// 市場別未註冊、被限制流量、回應格式錯誤或不完整時
+pub fn parse_close_price(
+    market_type: &str,
+    body: &str,
+    open_date: NaiveDate,
+) -> Result<Vec<(String, String)>, Error> {
+    match parse_body(market_type, body, open_date)? {
+        FetchOutcome::Prices(price) => Ok(price
+            .data
+            .into_iter()
+            .map(|x| (x[0].clone(), x[1].clone()))
+            .collect()),
+        FetchOutcome::NoData | FetchOutcome::Suspended => Ok(Vec::new()),
+        FetchOutcome::Retry(reason) => Err(Error::UpstreamRejected(reason)),
+    }
+}
+
+/// 解析來源回應的當月每日交易資料 (張、仟元換算為股、元)，查無資料或暫停交易時為空
+///
+/// # Errors
+/// 市場別未註冊、被限制流量、回應格式錯誤或不完整時
+pub fn parse_daily_bar(
+    market_type: &str,
+    body: &str,
+    open_date: NaiveDate,
+) -> Result<Vec<SecurityDailyBar>, Error> {
+    match parse_body(market_type, body, open_date)? {
+        FetchOutcome::Prices(price) => Ok(price.bars),
+        FetchOutcome::NoData | FetchOutcome::Suspended => Ok(Vec::new()),
+        FetchOutcome::Retry(reason) => Err(Error::UpstreamRejected(reason)),
+    }
+}
+
+/// 以指定開市日的任務解析來源回應
+fn parse_body(market_type: &str, body: &str, open_date: NaiveDate) -> Result<FetchOutcome, Error> {
+    let source = find_source(market_type)
+        .ok_or_else(|| Error::Parse(format!("unknown market {market_type}")))?;
+    if let Some(reason) = check_throttle(body) {
+        return Err(Error::RateLimited(reason.to_string()));
+    }
+
+    let task = SecurityTask {
+        row_id: String::new(),
+        open_date_year: open_date.format("%Y").to_string(),
+        open_date_month: open_date.format("%m").to_string(),
+        open_date_day: open_date.format("%d").to_string(),
+        open_date,
+        security_code: String::new(),
+        security_name: String::new(),
+        market_type: market_type.to_string(),
+        issue_date: String::new(),
+        exec_seed: String::new(),
+        exec_count: 0,
+        is_enabled: 1,
+        sort_no: 0,
+        exec_status: "WAIT".to_string(),
+        retry_count: 0,
+        last_error: String::new(),
+    };
+
+    source.parse(body, &task)
+}
+
+/// 檢查是否被限制流量，回傳原因 (預期 JSON 卻回傳 HTML、空白或封鎖訊息，JSON 僅檢查 `stat`)
+fn check_throttle(body: &str) -> Option<&'static str> {
+    let text = body.trim_start();
+    if text.is_empty() {
+        return Some("empty response");
+    }
+    if text.starts_with('<') {
+        return Some("html response");
+    }
+
+    let status = match serde_json::from_str::<Value>(text) {
+        Ok(json) => json
+            .get("stat")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        Err(_) => text.to_string(),
+    };
+    THROTTLE_MESSAGES
+        .iter()
+        .find(|x| status.contains(*x))
+        .copied()
+}
+
+/// 民國年月 (113/05)
+pub fn tw_ym(task: &SecurityTask) -> String {
+    RocDate::from(task.open_date).year_month()
+}
+
+/// 依欄位名稱取得位置
+pub fn field_index(fields: &[String], name: &str) -> Option<usize> {
+    fields.iter().position(|x| x.trim() == name)
+}
+
+/// 依當月資料判斷取得結果
+pub fn get_fetch_outcome(
+    raw_data: &[Vec<String>],
+    tw_ym: &str,
+    (date_index, price_index): (usize, usize),
+    (title, date, fields): (String, String, Vec<String>),
+    bars: Vec<SecurityDailyBar>,
+) -> FetchOutcome {
+    let month_data = raw_data
+        .iter()
+        .filter(|x| x.len() > max(date_index, price_index))
+        .filter(|x| x[date_index].trim().starts_with(tw_ym))
+        .cloned()
+        .collect::<Vec<Vec<String>>>();
+    if month_data.is_empty() {
+        return FetchOutcome::NoData;
+    }
+
+    let close_data = get_close_price(&month_data, date_index, price_index);
+    if close_data.is_empty() {
+        return FetchOutcome::Suspended;
+    }
+
+    FetchOutcome::Prices(MonthlyPrice {
+        status: "Y".to_string(),
+        title: html_decode(&title),
+        date: html_decode(&date),
+        fields: fields.iter().map(|x| html_decode(x)).collect(),
+        data: close_data
+            .iter()
+            .map(|row| row.iter().map(|x| html_decode(x)).collect())
+            .collect(),
+        bars,
+    })
+}
+
+/// 取得收盤價 (略過無成交)
+fn get_close_price(
+    data: &[Vec<String>],
+    date_index: usize,
+    price_index: usize,
+) -> Vec<Vec<String>> {
+    data.iter()
+        .filter(|x| {
+            BigDecimal::from_str(&x[price_index].replace(',', "")).unwrap_or(BigDecimal::zero())
+                > BigDecimal::zero()
+        })
+        .map(|x| vec![x[date_index].clone(), x[price_index].replace(',', "")])
+        .collect()
+}
+
+/// HTML decode
+fn html_decode(input: &str) -> String {
+    input
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace('*', "")
+        .replace("＊", "")
+}
+
+/// 任務執行紀錄
+fn run_task_log(task: &SecurityTask) {
+    let security_code = &task.security_code;
+    let market_type = &task.market_type;
+
+    let y = &task.open_date_year;
+    let m = &task.open_date_month;
+    let d = &task.open_date_day;
+    let open_date = format!("{y}{m}{d}");
+
+    event!(target: "security_api", Level::INFO, "send [ {0}: {1}({2}) ]", open_date, market_type, security_code);
+}
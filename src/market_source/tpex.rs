@@ -0,0 +1,120 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use reqwest::{Client, RequestBuilder};
+
+use crate::{
+    config::{self, model::SourceConfig},
+    response_data::model::{FetchOutcome, SecurityPriceTpex},
+    security_daily_bar,
+    security_task::model::SecurityTask,
+    Error,
+};
+
+use super::{model::MarketSource, service};
+
+/// 上櫃 (櫃買中心)
+pub struct TpexListed;
+
+/// 興櫃 (櫃買中心)
+pub struct TpexEmerging;
+
+impl MarketSource for TpexListed {
+    fn market_type(&self) -> &'static str {
+        "上櫃"
+    }
+
+    fn config(&self) -> &'static SourceConfig {
+        // 上櫃、興櫃共用主機與查詢流量
+        &config::service::get().tpex
+    }
+
+    fn build_request(&self, client: &Client, task: &SecurityTask) -> RequestBuilder {
+        let params = [
+            ("code", task.security_code.clone()),
+            ("date", open_date(task)),
+            ("id", String::new()),
+            ("response", "json".to_string()),
+        ];
+
+        client
+            .post(format!(
+                "{}/www/zh-tw/afterTrading/tradingStock",
+                self.host()
+            ))
+            .form(&params)
+    }
+
+    fn parse(&self, body: &str, task: &SecurityTask) -> Result<FetchOutcome, Error> {
+        parse_tpex(body, task, 6)
+    }
+}
+
+impl MarketSource for TpexEmerging {
+    fn market_type(&self) -> &'static str {
+        "興櫃"
+    }
+
+    fn config(&self) -> &'static SourceConfig {
+        &config::service::get().tpex
+    }
+
+    fn build_request(&self, client: &Client, task: &SecurityTask) -> RequestBuilder {
+        let params = [
+            ("type", "Monthly".to_string()),
+            ("date", open_date(task)),
+            ("code", task.security_code.clone()),
+            ("id", String::new()),
+            ("response", "json".to_string()),
+        ];
+
+        client
+            .post(format!("{}/www/zh-tw/emerging/historical", self.host()))
+            .form(&params)
+    }
+
+    fn parse(&self, body: &str, task: &SecurityTask) -> Result<FetchOutcome, Error> {
+        parse_tpex(body, task, 5)
+    }
+}
+
+/// 查詢日期 (YYYY/MM/DD)
+fn open_date(task: &SecurityTask) -> String {
+    let y = &task.open_date_year;
+    let m = &task.open_date_month;
+    let d = &task.open_date_day;
+    format!("{y}/{m}/{d}")
+}
+
+/// 解析櫃買中心回應
+fn parse_tpex(body: &str, task: &SecurityTask, price_index: usize) -> Result<FetchOutcome, Error> {
+    let tpex_json = serde_json::from_str::<SecurityPriceTpex>(body)?;
+
+    let Some(table) = tpex_json.tables.first() else {
+        return Ok(FetchOutcome::NoData);
+    };
+
+    if table.total_count == 0 {
+        return Ok(FetchOutcome::NoData);
+    }
+    if table.data.is_empty() {
+        return Ok(FetchOutcome::Retry(format!(
+            "tpex data missing: {}",
+            table.total_count
+        )));
+    }
+
+    let title = table.subtitle.clone();
+    let date = table.date.clone();
+    let fields = table.fields.clone();
+
+    let tw_ym = service::tw_ym(task);
+    let bars = security_daily_bar::service::parse_tpex(&tpex_json, task, &tw_ym);
+
+    Ok(service::get_fetch_outcome(
+        &table.data,
+        &tw_ym,
+        (0, price_index),
+        (title, date, fields),
+        bars,
+    ))
+}
@@ -0,0 +1,75 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use reqwest::{Client, RequestBuilder};
+
+use crate::{
+    config::{self, model::SourceConfig},
+    response_data::model::{FetchOutcome, SecurityPriceTwse},
+    security_daily_bar,
+    security_task::model::SecurityTask,
+    Error,
+};
+
+use super::{model::MarketSource, service};
+
+/// 上市 (證交所)
+pub struct Twse;
+
+impl MarketSource for Twse {
+    fn market_type(&self) -> &'static str {
+        "上市"
+    }
+
+    fn config(&self) -> &'static SourceConfig {
+        &config::service::get().twse
+    }
+
+    fn build_request(&self, client: &Client, task: &SecurityTask) -> RequestBuilder {
+        let y = &task.open_date_year;
+        let m = &task.open_date_month;
+        let d = &task.open_date_day;
+        let open_date = format!("{y}{m}{d}");
+
+        client
+            .get(format!("{}/rwd/zh/afterTrading/STOCK_DAY", self.host()))
+            .query(&[("date", &open_date)])
+            .query(&[("stockNo", &task.security_code)])
+            .query(&[("response", "json")])
+            .query(&[("_", &task.exec_seed)])
+    }
+
+    fn parse(&self, body: &str, task: &SecurityTask) -> Result<FetchOutcome, Error> {
+        let twse_json = serde_json::from_str::<SecurityPriceTwse>(body)?;
+
+        if "OK" != twse_json.stat {
+            return Ok(FetchOutcome::NoData);
+        }
+
+        let Some(raw_data) = &twse_json.data else {
+            return Ok(FetchOutcome::Retry(format!(
+                "twse data missing: {}",
+                twse_json.stat
+            )));
+        };
+
+        let title = twse_json.title.clone().unwrap_or_default();
+        let date = twse_json.date.clone().unwrap_or_default();
+        let fields = twse_json.fields.clone().unwrap_or_default();
+        let Some(price_index) = service::field_index(&fields, "收盤價") else {
+            return Ok(FetchOutcome::Retry(format!(
+                "twse close field missing: {fields:?}"
+            )));
+        };
+
+        let tw_ym = service::tw_ym(task);
+        let bars = security_daily_bar::service::parse_twse(&twse_json, task, &tw_ym);
+
+        Ok(service::get_fetch_outcome(
+            raw_data,
+            &tw_ym,
+            (0, price_index),
+            (title, date, fields),
+            bars,
+        ))
+    }
+}
@@ -0,0 +1,88 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use prometheus::{
+    GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry,
+};
+
+/// 請求耗時區間 (秒)
+const LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 20.0];
+
+/// 批次監控指標
+pub struct Metrics {
+    pub registry: Registry,
+    /// 各市場網路請求次數
+    pub http_requests: IntCounterVec,
+    /// 各市場網路請求耗時
+    pub http_duration: HistogramVec,
+    /// 各主機流量限制次數
+    pub throttles: IntCounterVec,
+    /// 各市場重試次數
+    pub retries: IntCounterVec,
+    /// 各資料表寫入筆數
+    pub rows_written: IntCounterVec,
+    /// 各工作執行次數
+    pub job_runs: IntCounterVec,
+    /// 各市場待執行證券任務數
+    pub task_backlog: IntGaugeVec,
+    /// 各工作最後成功時間
+    pub job_last_success: GaugeVec,
+}
+
+impl Metrics {
+    /// 建立並註冊指標
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some("security_api".to_string()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "網路請求次數"),
+            &["market"],
+        )?;
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "網路請求耗時")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["market"],
+        )?;
+        let throttles = IntCounterVec::new(
+            Opts::new("throttle_total", "流量限制次數"),
+            &["host", "market"],
+        )?;
+        let retries = IntCounterVec::new(Opts::new("retry_total", "重試次數"), &["market"])?;
+        let rows_written = IntCounterVec::new(
+            Opts::new("rows_written_total", "資料表寫入筆數"),
+            &["table", "op"],
+        )?;
+        let job_runs = IntCounterVec::new(
+            Opts::new("job_runs_total", "工作執行次數"),
+            &["job_code", "status"],
+        )?;
+        let task_backlog = IntGaugeVec::new(
+            Opts::new("security_task_backlog", "待執行證券任務數"),
+            &["market"],
+        )?;
+        let job_last_success = GaugeVec::new(
+            Opts::new("job_last_success_timestamp_seconds", "工作最後成功時間"),
+            &["job_code"],
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_duration.clone()))?;
+        registry.register(Box::new(throttles.clone()))?;
+        registry.register(Box::new(retries.clone()))?;
+        registry.register(Box::new(rows_written.clone()))?;
+        registry.register(Box::new(job_runs.clone()))?;
+        registry.register(Box::new(task_backlog.clone()))?;
+        registry.register(Box::new(job_last_success.clone()))?;
+
+        Ok(Metrics {
+            registry,
+            http_requests,
+            http_duration,
+            throttles,
+            retries,
+            rows_written,
+            job_runs,
+            task_backlog,
+            job_last_success,
+        })
+    }
+}
@@ -0,0 +1,127 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::{
+    sync::LazyLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{extract::State, http::StatusCode, routing::get, Router};
+use prometheus::{Encoder, TextEncoder};
+use tokio::net::TcpListener;
+use tracing::{event, Level};
+
+use super::model::Metrics;
+use crate::{config, job_run, repository::Repository, security_task};
+
+static METRICS: LazyLock<Option<Metrics>> = LazyLock::new(|| {
+    Metrics::new()
+        .inspect_err(|e| {
+            event!(target: "security_api", Level::ERROR, "metrics.new {}", e);
+        })
+        .ok()
+});
+
+/// 紀錄網路請求
+pub fn observe_request(market: &str, elapsed: Duration) {
+    if let Some(metrics) = METRICS.as_ref() {
+        metrics.http_requests.with_label_values(&[market]).inc();
+        metrics
+            .http_duration
+            .with_label_values(&[market])
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+/// 紀錄流量限制
+pub fn add_throttle(host: &str, market: &str) {
+    if let Some(metrics) = METRICS.as_ref() {
+        metrics.throttles.with_label_values(&[host, market]).inc();
+    }
+}
+
+/// 紀錄重試
+pub fn add_retry(market: &str) {
+    if let Some(metrics) = METRICS.as_ref() {
+        metrics.retries.with_label_values(&[market]).inc();
+    }
+}
+
+/// 紀錄寫入筆數 (op: insert/update)
+pub fn add_rows(table: &str, op: &str, cnt: u64) {
+    if let Some(metrics) = METRICS.as_ref() {
+        metrics
+            .rows_written
+            .with_label_values(&[table, op])
+            .inc_by(cnt);
+    }
+}
+
+/// 紀錄工作結束
+pub fn add_job_run(job_code: &str, status: &str) {
+    if let Some(metrics) = METRICS.as_ref() {
+        metrics
+            .job_runs
+            .with_label_values(&[job_code, status])
+            .inc();
+        if status == "EXIT" {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |x| x.as_secs_f64());
+            metrics
+                .job_last_success
+                .with_label_values(&[job_code])
+                .set(now);
+        }
+    }
+}
+
+/// 輸出指標 (待執行任務數與最後成功時間由資料庫更新)
+pub async fn render(repo: &Repository) -> Result<String, prometheus::Error> {
+    let Some(metrics) = METRICS.as_ref() else {
+        return Ok(String::new());
+    };
+
+    metrics.task_backlog.reset();
+    for (market_type, cnt) in
+        security_task::dao::find_all_by_backlog(repo, config::service::get().task.max_exec_count)
+            .await
+    {
+        metrics
+            .task_backlog
+            .with_label_values(&[market_type.as_str()])
+            .set(cnt);
+    }
+    for (job_code, last_success) in job_run::dao::find_all_by_last_success(repo).await {
+        let gauge = metrics
+            .job_last_success
+            .with_label_values(&[job_code.as_str()]);
+        if gauge.get() < last_success {
+            gauge.set(last_success);
+        }
+    }
+
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&metrics.registry.gather(), &mut buffer)?;
+    String::from_utf8(buffer).map_err(|e| prometheus::Error::Msg(e.to_string()))
+}
+
+/// 指標路由
+pub fn router() -> Router<Repository> {
+    Router::new().route("/metrics", get(get_metrics))
+}
+
+/// 啟動指標服務
+pub async fn serve(repo: &Repository, addr: &str) -> Result<(), std::io::Error> {
+    let listener = TcpListener::bind(addr).await?;
+    event!(target: "security_api", Level::INFO, "metrics listen on {}", listener.local_addr()?);
+
+    axum::serve(listener, router().with_state(repo.clone())).await
+}
+
+/// 取得指標
+async fn get_metrics(State(repo): State<Repository>) -> Result<String, (StatusCode, String)> {
+    render(&repo).await.map_err(|e| {
+        event!(target: "security_api", Level::ERROR, "metrics.render {}", &e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })
+}
@@ -1,9 +1,51 @@
 #![warn(clippy::all, clippy::pedantic)]
 
-use std::env;
+use std::{env, str::FromStr, time::Duration};
 
 use dotenvy::dotenv;
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
+
+use crate::{
+    config::{self, model::DatabaseConfig},
+    Error,
+};
+
+/// 連線池設定
+#[derive(Debug, Clone)]
+pub struct PoolOption {
+    /// 最大連線數
+    pub max_connections: u32,
+    /// 最小連線數
+    pub min_connections: u32,
+    /// 取得連線逾時 (秒)
+    pub acquire_timeout: u64,
+    /// 閒置連線逾時 (秒)
+    pub idle_timeout: u64,
+    /// 預備語句快取數
+    pub statement_cache: usize,
+}
+
+impl Default for PoolOption {
+    fn default() -> Self {
+        PoolOption::from_config(&config::model::Config::default().database)
+    }
+}
+
+impl PoolOption {
+    /// 依設定檔 `[database]` 建立
+    pub(crate) fn from_config(database: &DatabaseConfig) -> Self {
+        PoolOption {
+            max_connections: database.max_connections,
+            min_connections: database.min_connections,
+            acquire_timeout: database.acquire_timeout_secs,
+            idle_timeout: database.idle_timeout_secs,
+            statement_cache: database.statement_cache,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Repository {
@@ -11,18 +53,41 @@ pub struct Repository {
 }
 
 impl Repository {
-    pub async fn new() -> Self {
+    /// 建立資料庫連線池 (連線字串讀取 `.env`，連線池依設定檔 `[database]`)
+    ///
+    /// # Errors
+    /// 尚未載入設定、未設定 `DATABASE_URL` 或無法連線時
+    pub async fn new() -> Result<Self, Error> {
+        let config = config::service::try_get()?;
         dotenv().ok();
 
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let database_url = env::var("DATABASE_URL")
+            .map_err(|_| sqlx::Error::Configuration("DATABASE_URL must be set".into()))?;
+
+        let option = PoolOption::from_config(&config.database);
+        Repository::connect(&database_url, &option).await
+    }
+
+    /// 依設定建立資料庫連線池 (需先載入設定，各服務皆會讀取)
+    ///
+    /// # Errors
+    /// 尚未載入設定、連線字串錯誤或無法連線時
+    pub async fn connect(database_url: &str, option: &PoolOption) -> Result<Self, Error> {
+        config::service::try_get()?;
+
+        let connect_option = PgConnectOptions::from_str(database_url)?
+            .statement_cache_capacity(option.statement_cache);
 
         let db_pool = PgPoolOptions::new()
-            .connect(&database_url)
-            .await
-            .expect(&format!("Error connecting to {}", database_url));
+            .max_connections(option.max_connections)
+            .min_connections(option.min_connections)
+            .acquire_timeout(Duration::from_secs(option.acquire_timeout))
+            .idle_timeout(Duration::from_secs(option.idle_timeout))
+            .connect_with(connect_option)
+            .await?;
 
-        Repository {
+        Ok(Repository {
             connection: db_pool,
-        }
+        })
     }
 }
@@ -8,9 +8,8 @@ use crate::{repository::Repository, security_task::model::SecurityTask};
 
 use super::model::ResponseData;
 
-pub async fn create(data: ResponseData) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn create(repo: &Repository, data: ResponseData) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -18,11 +17,12 @@ pub async fn create(data: ResponseData) -> Result<u64, sqlx::Error> {
             open_date_year
           , open_date_month
           , open_date_day
+          , open_date
           , exec_code
           , data_content
           , created_date
           , updated_date
-        ) VALUES ( $1, $2, $3, $4, $5, $6, $7 )
+        ) VALUES ( $1, $2, $3, make_date($1::integer, $2::integer, $3::integer), $4, $5, $6, $7 )
     ",
     )
     .bind(data.open_date_year)
@@ -32,7 +32,7 @@ pub async fn create(data: ResponseData) -> Result<u64, sqlx::Error> {
     .bind(data.data_content)
     .bind(Local::now())
     .bind(Local::now())
-    .execute(&conn)
+    .execute(conn)
     .await
     {
         Ok(cnt) => Ok(cnt.rows_affected()),
@@ -40,9 +40,8 @@ pub async fn create(data: ResponseData) -> Result<u64, sqlx::Error> {
     }
 }
 
-pub async fn modify(data: ResponseData) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn modify(repo: &Repository, data: ResponseData) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -50,6 +49,7 @@ pub async fn modify(data: ResponseData) -> Result<u64, sqlx::Error> {
            SET open_date_year = $1
              , open_date_month = $2
              , open_date_day = $3
+             , open_date = make_date($1::integer, $2::integer, $3::integer)
              , exec_code = $4
              , data_content = $5
              , updated_date = $6
@@ -63,7 +63,7 @@ pub async fn modify(data: ResponseData) -> Result<u64, sqlx::Error> {
     .bind(data.data_content)
     .bind(Local::now())
     .bind(data.row_id)
-    .execute(&conn)
+    .execute(conn)
     .await
     {
         Ok(cnt) => Ok(cnt.rows_affected()),
@@ -72,13 +72,13 @@ pub async fn modify(data: ResponseData) -> Result<u64, sqlx::Error> {
 }
 
 pub async fn find_one(
+    repo: &Repository,
     q_year: &str,
     q_month: &str,
     q_day: &str,
     q_exec_code: &str,
 ) -> Option<ResponseData> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -109,7 +109,7 @@ pub async fn find_one(
         exec_code: row.get("exec_code"),
         data_content: row.get("data_content"),
     })
-    .fetch_optional(&conn)
+    .fetch_optional(conn)
     .await
     {
         Ok(row) => row,
@@ -120,9 +120,8 @@ pub async fn find_one(
     }
 }
 
-pub async fn find_one_by_max(task: &SecurityTask) -> Option<ResponseData> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_one_by_max(repo: &Repository, task: &SecurityTask) -> Option<ResponseData> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -135,16 +134,13 @@ pub async fn find_one_by_max(task: &SecurityTask) -> Option<ResponseData> {
              , created_date
              , updated_date
           FROM response_data
-         WHERE open_date_year = $1 
-           AND open_date_month = $2
-           AND open_date_day >= $3
-           AND exec_code = $4
+         WHERE exec_code = $1
+           AND open_date >= $2
+           AND open_date < date_trunc('month', $2::date) + interval '1 month'
          ",
     )
-    .bind(task.open_date_year.clone())
-    .bind(task.open_date_month.clone())
-    .bind(task.open_date_day.clone())
     .bind(task.security_code.clone())
+    .bind(task.open_date)
     .map(|row: PgRow| ResponseData {
         row_id: row.get("row_id"),
         open_date_year: row.get("open_date_year"),
@@ -153,7 +149,7 @@ pub async fn find_one_by_max(task: &SecurityTask) -> Option<ResponseData> {
         exec_code: row.get("exec_code"),
         data_content: row.get("data_content"),
     })
-    .fetch_optional(&conn)
+    .fetch_optional(conn)
     .await
     {
         Ok(row) => row,
@@ -164,9 +160,8 @@ pub async fn find_one_by_max(task: &SecurityTask) -> Option<ResponseData> {
     }
 }
 
-pub async fn find_one_by_min(task: &SecurityTask) -> Option<ResponseData> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_one_by_min(repo: &Repository, task: &SecurityTask) -> Option<ResponseData> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -179,16 +174,13 @@ pub async fn find_one_by_min(task: &SecurityTask) -> Option<ResponseData> {
              , created_date
              , updated_date
           FROM response_data
-         WHERE open_date_year = $1 
-           AND open_date_month = $2
-           AND open_date_day <= $3
-           AND exec_code = $4
+         WHERE exec_code = $1
+           AND open_date >= date_trunc('month', $2::date)
+           AND open_date <= $2
          ",
     )
-    .bind(task.open_date_year.clone())
-    .bind(task.open_date_month.clone())
-    .bind(task.open_date_day.clone())
     .bind(task.security_code.clone())
+    .bind(task.open_date)
     .map(|row: PgRow| ResponseData {
         row_id: row.get("row_id"),
         open_date_year: row.get("open_date_year"),
@@ -197,7 +189,7 @@ pub async fn find_one_by_min(task: &SecurityTask) -> Option<ResponseData> {
         exec_code: row.get("exec_code"),
         data_content: row.get("data_content"),
     })
-    .fetch_optional(&conn)
+    .fetch_optional(conn)
     .await
     {
         Ok(row) => row,
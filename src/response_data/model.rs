@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::security_daily_bar::model::SecurityDailyBar;
+
 #[derive(Debug, Clone)]
 pub struct ResponseData {
     pub row_id: String,
@@ -23,12 +25,11 @@ impl std::fmt::Display for ResponseData {
 
         write!(
             f,
-            r#"{0}, 
-            open_date: {1}{2}{3}, 
-            exec_code: {4}, 
-            data_content: {5}
-            "#,
-            row_id, open_date_year, open_date_month, open_date_day, exec_code, data_content
+            r"{row_id}, 
+            open_date: {open_date_year}{open_date_month}{open_date_day}, 
+            exec_code: {exec_code}, 
+            data_content: {data_content}
+            "
         )
     }
 }
@@ -77,4 +78,19 @@ pub struct MonthlyPrice {
     pub date: String,
     pub fields: Vec<String>,
     pub data: Vec<Vec<String>>,
+    #[serde(default)]
+    pub bars: Vec<SecurityDailyBar>,
+}
+
+/// 價格取得結果
+#[derive(Debug, Clone)]
+pub enum FetchOutcome {
+    /// 取得當月收盤價
+    Prices(MonthlyPrice),
+    /// 查無當月資料
+    NoData,
+    /// 回應不完整，需重試
+    Retry(String),
+    /// 當月有資料但無成交 (暫停交易)
+    Suspended,
 }
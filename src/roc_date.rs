@@ -0,0 +1,107 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::Error;
+
+/// 民國紀年與西元紀年差距
+const ROC_OFFSET: i32 = 1911;
+
+/// 民國日期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RocDate(NaiveDate);
+
+impl RocDate {
+    /// 依民國年月日建立
+    ///
+    /// # Errors
+    /// 民國年小於 1 或日期不存在時
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Result<Self, Error> {
+        if year < 1 {
+            return Err(Error::Parse(format!("roc year {year} out of range")));
+        }
+        NaiveDate::from_ymd_opt(year + ROC_OFFSET, month, day)
+            .map(RocDate)
+            .ok_or_else(|| Error::Parse(format!("roc date {year}/{month}/{day} out of range")))
+    }
+
+    /// 民國年
+    #[must_use]
+    pub fn year(self) -> i32 {
+        self.0.year() - ROC_OFFSET
+    }
+
+    /// 西元日期
+    #[must_use]
+    pub fn to_naive(self) -> NaiveDate {
+        self.0
+    }
+
+    /// 民國年月 (113/05)
+    #[must_use]
+    pub fn year_month(self) -> String {
+        format!("{0}/{1:02}", self.year(), self.0.month())
+    }
+
+    /// 收盤日期 (0113/05/02)
+    #[must_use]
+    pub fn price_date(self) -> String {
+        format!("{self:0>10}")
+    }
+}
+
+impl From<NaiveDate> for RocDate {
+    fn from(date: NaiveDate) -> Self {
+        RocDate(date)
+    }
+}
+
+impl From<RocDate> for NaiveDate {
+    fn from(date: RocDate) -> Self {
+        date.0
+    }
+}
+
+/// 解析 113/05/02、113年05月02日、1130502
+impl FromStr for RocDate {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::Parse(format!("roc date `{value}`"));
+
+        let text = value.trim();
+        let parts: Vec<&str> = if text.contains('/') {
+            text.split('/').collect()
+        } else if let Some(text) = text.strip_suffix('日') {
+            text.split(['年', '月']).collect()
+        } else if text.len() > 4 && text.bytes().all(|x| x.is_ascii_digit()) {
+            let (year, month_day) = text.split_at(text.len() - 4);
+            let (month, day) = month_day.split_at(2);
+            vec![year, month, day]
+        } else {
+            Vec::new()
+        };
+
+        let [year, month, day] = parts[..] else {
+            return Err(invalid());
+        };
+        let year = year.trim().parse::<i32>().map_err(|_| invalid())?;
+        let month = month.trim().parse::<u32>().map_err(|_| invalid())?;
+        let day = day.trim().parse::<u32>().map_err(|_| invalid())?;
+
+        RocDate::from_ymd(year, month, day).map_err(|_| invalid())
+    }
+}
+
+impl std::fmt::Display for RocDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.pad(&format!(
+            "{0}/{1:02}/{2:02}",
+            self.year(),
+            self.0.month(),
+            self.0.day()
+        ))
+    }
+}
@@ -0,0 +1,117 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, NaiveDate};
+use tokio::sync::oneshot;
+use tracing::{event, Level};
+
+use crate::{
+    calendar_data, config, daily_task, job::service::JobRegistry, repository::Repository,
+    task_setting, Error, TaskOption,
+};
+
+/// 最短等待時間
+const MIN_WAIT: Duration = Duration::from_secs(1);
+/// 最長等待秒數 (重新讀取 `task_setting`)
+const MAX_WAIT_SECS: u64 = 60;
+/// 最長等待時間
+const MAX_WAIT: Duration = Duration::from_secs(MAX_WAIT_SECS);
+
+/// 依 `task_setting` 設定的間隔 (對齊收盤時間) 執行已啟用的工作，收到 Ctrl-C 時結束
+pub async fn run_scheduler(repo: &Repository, registry: &JobRegistry) -> Result<(), Error> {
+    let option = TaskOption::default();
+    let at = config::service::get().market.close_time;
+    let mut next_runs = HashMap::<String, DateTime<Local>>::new();
+    let mut invalid_jobs = HashSet::<String>::new();
+    let mut task_date: Option<NaiveDate> = None;
+
+    // 啟動時即註冊 Ctrl-C，執行工作期間收到時於工作結束後停止
+    let (stop_tx, mut stop_rx) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = stop_tx.send(());
+        }
+    });
+
+    event!(target: "security_api", Level::INFO, "scheduler start");
+    loop {
+        let today = Local::now().date_naive();
+        if task_date != Some(today) {
+            prepare_task(repo, registry, today).await;
+            task_date = Some(today);
+        }
+
+        let mut job_codes = HashSet::<String>::new();
+        let settings = task_setting::dao::find_all_by_enabled(repo).await;
+        for setting in settings {
+            let Some(job_code) = setting.job_code.clone() else {
+                continue;
+            };
+            // 同一工作設定於多個群組時以排序較前者為準
+            if !job_codes.insert(job_code.clone()) {
+                continue;
+            }
+
+            let now = Local::now();
+            let next_run = task_setting::service::next_run_time(&setting, at, now);
+            let Some(next_run) = next_run.filter(|_| registry.contains(&job_code)) else {
+                if invalid_jobs.insert(job_code.clone()) {
+                    event!(target: "security_api", Level::WARN, "scheduler skip {}", &setting);
+                }
+                continue;
+            };
+            invalid_jobs.remove(&job_code);
+
+            // 啟動或新增工作時等待第一個排程時間，不立即執行
+            if *next_runs.entry(job_code.clone()).or_insert(next_run) > now {
+                continue;
+            }
+
+            match daily_task::service::exec_job_task(repo, registry, &job_code, &option).await {
+                Ok(summary) if summary.succeeded.is_empty() && summary.failed.is_empty() => {}
+                Ok(summary) => {
+                    event!(target: "security_api", Level::INFO, "scheduler.{} summary {}", &job_code, &summary);
+                }
+                Err(e) => {
+                    event!(target: "security_api", Level::ERROR, "scheduler.{} {}", &job_code, &e);
+                }
+            }
+
+            if let Some(next_run) = task_setting::service::next_run_time(&setting, at, Local::now())
+            {
+                next_runs.insert(job_code, next_run);
+            }
+        }
+        next_runs.retain(|k, _| job_codes.contains(k));
+
+        let now = Local::now();
+        let wait = next_runs
+            .values()
+            .min()
+            .and_then(|x| (*x - now).to_std().ok())
+            .unwrap_or(MAX_WAIT)
+            .clamp(MIN_WAIT, MAX_WAIT);
+
+        tokio::select! {
+            () = tokio::time::sleep(wait) => {}
+            _ = &mut stop_rx => {
+                event!(target: "security_api", Level::INFO, "scheduler stop");
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// 換日時新增行事曆與每日任務
+async fn prepare_task(repo: &Repository, registry: &JobRegistry, today: NaiveDate) {
+    if let Err(e) = calendar_data::service::insert_calendar_data(repo, false).await {
+        event!(target: "security_api", Level::ERROR, "scheduler.insert_calendar_data {}", &e);
+    }
+    if let Err(e) = daily_task::service::insert_task_data(repo, registry, today, false).await {
+        event!(target: "security_api", Level::ERROR, "scheduler.insert_task_data {}", &e);
+    }
+}
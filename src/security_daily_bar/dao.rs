@@ -0,0 +1,81 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use chrono::Local;
+use sqlx::PgConnection;
+
+use super::model::SecurityDailyBar;
+
+pub async fn create(
+    trax_conn: &mut PgConnection,
+    data: SecurityDailyBar,
+) -> Result<u64, sqlx::Error> {
+    match sqlx::query(
+        r"
+        INSERT INTO security_daily_bar(
+            open_date_year
+          , open_date_month
+          , open_date_day
+          , open_date
+          , security_code
+          , market_type
+          , price_date
+          , trade_date
+          , trade_volume
+          , trade_value
+          , price_open
+          , price_high
+          , price_low
+          , price_close
+          , price_change
+          , trade_count
+          , created_date
+          , updated_date
+        ) VALUES ( $1, $2, $3, make_date($1::integer, $2::integer, $3::integer), $4, $5, $6,
+         $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17 )
+    ",
+    )
+    .bind(data.open_date_year)
+    .bind(data.open_date_month)
+    .bind(data.open_date_day)
+    .bind(data.security_code)
+    .bind(data.market_type)
+    .bind(data.price_date)
+    .bind(data.trade_date)
+    .bind(data.trade_volume)
+    .bind(data.trade_value)
+    .bind(data.price_open)
+    .bind(data.price_high)
+    .bind(data.price_low)
+    .bind(data.price_close)
+    .bind(data.price_change)
+    .bind(data.trade_count)
+    .bind(Local::now())
+    .bind(Local::now())
+    .execute(trax_conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn remove(
+    trax_conn: &mut PgConnection,
+    data: &SecurityDailyBar,
+) -> Result<u64, sqlx::Error> {
+    match sqlx::query(
+        r"
+        DELETE FROM security_daily_bar 
+         WHERE price_date = $1
+           AND security_code = $2
+    ",
+    )
+    .bind(&data.price_date)
+    .bind(&data.security_code)
+    .execute(trax_conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
+    }
+}
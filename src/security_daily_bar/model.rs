@@ -0,0 +1,63 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityDailyBar {
+    pub row_id: String,
+    pub open_date_year: String,
+    pub open_date_month: String,
+    pub open_date_day: String,
+    pub security_code: String,
+    pub market_type: String,
+    pub price_date: String,
+    pub trade_date: NaiveDate,
+    pub trade_volume: BigDecimal,
+    pub trade_value: BigDecimal,
+    pub price_open: BigDecimal,
+    pub price_high: BigDecimal,
+    pub price_low: BigDecimal,
+    pub price_close: BigDecimal,
+    pub price_change: BigDecimal,
+    pub trade_count: BigDecimal,
+}
+
+impl std::fmt::Display for SecurityDailyBar {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let row_id = self.row_id.clone();
+        let open_date_year = self.open_date_year.clone();
+        let open_date_month = self.open_date_month.clone();
+        let open_date_day = self.open_date_day.clone();
+        let security_code = self.security_code.clone();
+        let market_type = self.market_type.clone();
+        let price_date = self.price_date.clone();
+        let trade_volume = self.trade_volume.clone();
+        let trade_value = self.trade_value.clone();
+        let price_open = self.price_open.clone();
+        let price_high = self.price_high.clone();
+        let price_low = self.price_low.clone();
+        let price_close = self.price_close.clone();
+        let price_change = self.price_change.clone();
+        let trade_count = self.trade_count.clone();
+
+        write!(
+            f,
+            r"{row_id}, 
+            open_date: {open_date_year}{open_date_month}{open_date_day}, 
+            security_code: {security_code}, 
+            market_type: {market_type}, 
+            price_date: {price_date}, 
+            trade_volume: {trade_volume}, 
+            trade_value: {trade_value}, 
+            price_open: {price_open}, 
+            price_high: {price_high}, 
+            price_low: {price_low}, 
+            price_close: {price_close}, 
+            price_change: {price_change}, 
+            trade_count: {trade_count}
+            "
+        )
+    }
+}
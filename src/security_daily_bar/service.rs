@@ -0,0 +1,180 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::str::FromStr;
+
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::PgConnection;
+use tracing::{event, Level};
+
+use crate::{
+    job_run,
+    response_data::model::{SecurityPriceTpex, SecurityPriceTwse},
+    roc_date::RocDate,
+    security_task::model::SecurityTask,
+    Error,
+};
+
+use super::{dao, model::SecurityDailyBar};
+
+/// 欄位位置
+#[derive(Debug, Default)]
+struct BarColumns {
+    date: Option<usize>,
+    volume: Option<(usize, u32)>,
+    value: Option<(usize, u32)>,
+    open: Option<usize>,
+    high: Option<usize>,
+    low: Option<usize>,
+    close: Option<usize>,
+    change: Option<usize>,
+    count: Option<usize>,
+}
+
+/// 解析證交所回應
+pub fn parse_twse(
+    twse_json: &SecurityPriceTwse,
+    task: &SecurityTask,
+    tw_ym: &str,
+) -> Vec<SecurityDailyBar> {
+    let fields = twse_json.fields.clone().unwrap_or_default();
+    let data = twse_json.data.clone().unwrap_or_default();
+
+    parse_rows(&fields, &data, task, tw_ym)
+}
+
+/// 解析櫃買中心回應
+pub fn parse_tpex(
+    tpex_json: &SecurityPriceTpex,
+    task: &SecurityTask,
+    tw_ym: &str,
+) -> Vec<SecurityDailyBar> {
+    tpex_json
+        .tables
+        .first()
+        .map(|table| parse_rows(&table.fields, &table.data, task, tw_ym))
+        .unwrap_or_default()
+}
+
+/// 依欄位名稱解析當月交易資料
+fn parse_rows(
+    fields: &[String],
+    data: &[Vec<String>],
+    task: &SecurityTask,
+    tw_ym: &str,
+) -> Vec<SecurityDailyBar> {
+    let columns = get_bar_columns(fields);
+    let Some(date_index) = columns.date else {
+        return Vec::new();
+    };
+
+    data.iter()
+        .filter(|row| {
+            row.get(date_index)
+                .is_some_and(|x| x.trim().starts_with(tw_ym))
+        })
+        .filter(|row| has_trade(row, &columns))
+        .filter_map(|row| {
+            let price_date = RocDate::from_str(&row[date_index]).ok()?;
+            Some((row, price_date))
+        })
+        .map(|(row, price_date)| SecurityDailyBar {
+            row_id: String::new(),
+            open_date_year: task.open_date_year.clone(),
+            open_date_month: task.open_date_month.clone(),
+            open_date_day: task.open_date_day.clone(),
+            security_code: task.security_code.clone(),
+            market_type: task.market_type.clone(),
+            price_date: price_date.price_date(),
+            trade_date: price_date.to_naive(),
+            trade_volume: get_scaled_value(row, columns.volume),
+            trade_value: get_scaled_value(row, columns.value),
+            price_open: get_value(row, columns.open),
+            price_high: get_value(row, columns.high),
+            price_low: get_value(row, columns.low),
+            price_close: get_value(row, columns.close),
+            price_change: get_value(row, columns.change),
+            trade_count: get_value(row, columns.count),
+        })
+        .collect()
+}
+
+/// 取得欄位位置 (張、仟股、仟元換算為股、元)
+fn get_bar_columns(fields: &[String]) -> BarColumns {
+    let mut columns = BarColumns::default();
+
+    for (index, field) in fields.iter().enumerate() {
+        let name = field.split_whitespace().collect::<String>();
+
+        if name.starts_with("日期") {
+            columns.date = Some(index);
+        } else if name.contains("股數") {
+            columns.volume = Some((index, 1));
+        } else if name.contains("張數") || name.contains("仟股") {
+            columns.volume = Some((index, 1000));
+        } else if name.contains("仟元") {
+            columns.value = Some((index, 1000));
+        } else if name.contains("金額") {
+            columns.value = Some((index, 1));
+        } else if name.starts_with("開盤") {
+            columns.open = Some(index);
+        } else if name.starts_with("最高") {
+            columns.high = Some(index);
+        } else if name.starts_with("最低") {
+            columns.low = Some(index);
+        } else if name.starts_with("收盤") {
+            columns.close = Some(index);
+        } else if name.starts_with("日均價") {
+            columns.close = columns.close.or(Some(index));
+        } else if name.starts_with("漲跌") {
+            columns.change = Some(index);
+        } else if name.ends_with("筆數") {
+            columns.count = Some(index);
+        }
+    }
+
+    columns
+}
+
+/// 是否有成交 (開高低收為 -- 或 0 的列略過，不寫入 0 價格)
+fn has_trade(row: &[String], columns: &BarColumns) -> bool {
+    let Some(close) = columns.close else {
+        return false;
+    };
+
+    [columns.open, columns.high, columns.low, Some(close)]
+        .into_iter()
+        .flatten()
+        .all(|index| get_value(row, Some(index)) > BigDecimal::zero())
+}
+
+/// 取得數值 (無法解析為 0)
+fn get_value(row: &[String], index: Option<usize>) -> BigDecimal {
+    index
+        .and_then(|i| row.get(i))
+        .map(|x| x.replace([',', '+', 'X', 'x', ' '], ""))
+        .and_then(|x| BigDecimal::from_str(&x).ok())
+        .unwrap_or(BigDecimal::zero())
+}
+
+/// 取得換算後數值
+fn get_scaled_value(row: &[String], column: Option<(usize, u32)>) -> BigDecimal {
+    column.map_or(BigDecimal::zero(), |(index, scale)| {
+        get_value(row, Some(index)) * BigDecimal::from(scale)
+    })
+}
+
+/// 寫入每日交易資料
+pub async fn save_bars(
+    trax_conn: &mut PgConnection,
+    bars: &[SecurityDailyBar],
+) -> Result<(), Error> {
+    for bar in bars {
+        event!(target: "security_api", Level::DEBUG, "SecurityDailyBar: {}", &bar);
+
+        dao::remove(trax_conn, bar).await?;
+        let cnt = dao::create(trax_conn, bar.clone()).await?;
+        job_run::service::count_inserted("security_daily_bar", cnt);
+    }
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 #![warn(clippy::all, clippy::pedantic)]
 
-use chrono::Local;
+use chrono::{Local, NaiveDate};
 use sqlx::{postgres::PgRow, PgConnection, Row};
 use tracing::{event, Level};
 
@@ -15,9 +15,11 @@ pub async fn create(trax_conn: &mut PgConnection, data: SecurityPrice) -> Result
             open_date_year
           , open_date_month
           , open_date_day
+          , open_date
           , security_code 
           , security_name 
           , price_date 
+          , trade_date 
           , price_close 
           , price_avg 
           , price_hight 
@@ -26,16 +28,18 @@ pub async fn create(trax_conn: &mut PgConnection, data: SecurityPrice) -> Result
           , price_lowest_avg 
           , created_date
           , updated_date
-        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, 
-         $8, $9, $10, $11, $12, $13, $14 )
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, 
+         $9, $10, $11, $12, $13, $14, $15, $16 )
     ",
     )
     .bind(data.open_date_year)
     .bind(data.open_date_month)
     .bind(data.open_date_day)
+    .bind(data.open_date)
     .bind(data.security_code)
     .bind(data.security_name)
     .bind(data.price_date)
+    .bind(data.trade_date)
     .bind(data.price_close)
     .bind(data.price_avg)
     .bind(data.price_hight)
@@ -52,9 +56,8 @@ pub async fn create(trax_conn: &mut PgConnection, data: SecurityPrice) -> Result
     }
 }
 
-pub async fn modify(data: SecurityPrice) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn modify(repo: &Repository, data: SecurityPrice) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -62,25 +65,29 @@ pub async fn modify(data: SecurityPrice) -> Result<u64, sqlx::Error> {
            SET open_date_year = $1
              , open_date_month = $2
              , open_date_day = $3
-             , security_code = $4
-             , security_name = $5
-             , price_date = $6
-             , price_close = $7
-             , price_avg = $8
-             , price_hight = $9
-             , price_hight_avg = $10
-             , price_lowest = $11
-             , price_lowest_avg = $12
-             , updated_date = $13
-         WHERE row_id = $14
+             , open_date = $4
+             , security_code = $5
+             , security_name = $6
+             , price_date = $7
+             , trade_date = $8
+             , price_close = $9
+             , price_avg = $10
+             , price_hight = $11
+             , price_hight_avg = $12
+             , price_lowest = $13
+             , price_lowest_avg = $14
+             , updated_date = $15
+         WHERE row_id = $16
     ",
     )
     .bind(data.open_date_year)
     .bind(data.open_date_month)
     .bind(data.open_date_day)
+    .bind(data.open_date)
     .bind(data.security_code)
     .bind(data.security_name)
     .bind(data.price_date)
+    .bind(data.trade_date)
     .bind(data.price_close)
     .bind(data.price_avg)
     .bind(data.price_hight)
@@ -89,7 +96,7 @@ pub async fn modify(data: SecurityPrice) -> Result<u64, sqlx::Error> {
     .bind(data.price_lowest_avg)
     .bind(Local::now())
     .bind(data.row_id)
-    .execute(&conn)
+    .execute(conn)
     .await
     {
         Ok(cnt) => Ok(cnt.rows_affected()),
@@ -97,10 +104,7 @@ pub async fn modify(data: SecurityPrice) -> Result<u64, sqlx::Error> {
     }
 }
 
-pub async fn remove(
-    trax_conn: &mut PgConnection,
-    data: SecurityPrice,
-) -> Result<u64, sqlx::Error> {
+pub async fn remove(trax_conn: &mut PgConnection, data: SecurityPrice) -> Result<u64, sqlx::Error> {
     match sqlx::query(
         r"
         DELETE FROM security_price 
@@ -118,9 +122,8 @@ pub async fn remove(
     }
 }
 
-pub async fn find_all_by_res(q_year: &str, q_month: &str) -> Vec<ResposePrice> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_all_by_res(repo: &Repository, q_year: &str, q_month: &str) -> Vec<ResposePrice> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r" 
@@ -128,6 +131,7 @@ pub async fn find_all_by_res(q_year: &str, q_month: &str) -> Vec<ResposePrice> {
              , st.open_date_year
              , st.open_date_month
              , st.open_date_day
+             , st.open_date
              , st.security_code
              , st.security_name
              , st.market_type
@@ -153,12 +157,13 @@ pub async fn find_all_by_res(q_year: &str, q_month: &str) -> Vec<ResposePrice> {
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         security_code: row.get("security_code"),
         security_name: row.get("security_name"),
         market_type: row.get("market_type"),
         data_content: row.get("data_content"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
         Ok(rows) => rows,
@@ -170,12 +175,12 @@ pub async fn find_all_by_res(q_year: &str, q_month: &str) -> Vec<ResposePrice> {
 }
 
 pub async fn find_all(
+    repo: &Repository,
     q_year: &str,
     q_month: &str,
     q_security_code: &str,
 ) -> Vec<SecurityPrice> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+    let conn = &repo.connection;
 
     match sqlx::query(
         r" 
@@ -183,9 +188,11 @@ pub async fn find_all(
              , sp.open_date_year
              , sp.open_date_month
              , sp.open_date_day
+             , sp.open_date
              , sp.security_code 
              , sp.security_name 
-             , sp.price_date 
+             , sp.price_date
+             , sp.trade_date 
              , sp.price_close 
              , sp.price_avg 
              , sp.price_hight 
@@ -206,9 +213,11 @@ pub async fn find_all(
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         security_code: row.get("security_code"),
         security_name: row.get("security_name"),
         price_date: row.get("price_date"),
+        trade_date: row.get("trade_date"),
         price_close: row.get("price_close"),
         price_avg: row.get("price_avg"),
         price_hight: row.get("price_hight"),
@@ -216,7 +225,7 @@ pub async fn find_all(
         price_lowest: row.get("price_lowest"),
         price_lowest_avg: row.get("price_lowest_avg"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
         Ok(rows) => rows,
@@ -228,12 +237,12 @@ pub async fn find_all(
 }
 
 pub async fn find_all_by_code(
-    q_open_date: &str,
-    q_price_date: &str,
+    repo: &Repository,
+    q_open_date: NaiveDate,
+    q_trade_date: NaiveDate,
     q_security_code: &str,
 ) -> Vec<SecurityPrice> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+    let conn = &repo.connection;
 
     match sqlx::query(
         r" 
@@ -241,9 +250,11 @@ pub async fn find_all_by_code(
              , sp.open_date_year
              , sp.open_date_month
              , sp.open_date_day
+             , sp.open_date
              , sp.security_code
              , sp.security_name
              , sp.price_date
+             , sp.trade_date
              , sp.price_close
              , sp.price_avg
              , sp.price_hight
@@ -253,14 +264,13 @@ pub async fn find_all_by_code(
              , sp.created_date
              , sp.updated_date
           FROM security_price sp
-          WHERE sp.price_date <= $1
-            AND sp.price_date !='月平均收盤價' 
+          WHERE sp.trade_date <= $1
             AND sp.security_code = $2
-            AND concat(sp.open_date_year, sp.open_date_month, sp.open_date_day) <= $3
-         ORDER BY sp.open_date_year, sp.open_date_month, sp.open_date_day, sp.price_date, sp.security_code
+            AND sp.open_date <= $3
+         ORDER BY sp.open_date, sp.trade_date, sp.security_code
     ",
     )
-    .bind(q_price_date)
+    .bind(q_trade_date)
     .bind(q_security_code)
     .bind(q_open_date)
     .map(|row: PgRow| SecurityPrice {
@@ -268,9 +278,11 @@ pub async fn find_all_by_code(
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         security_code: row.get("security_code"),
         security_name: row.get("security_name"),
         price_date: row.get("price_date"),
+        trade_date: row.get("trade_date"),
         price_close: row.get("price_close"),
         price_avg: row.get("price_avg"),
         price_hight: row.get("price_hight"),
@@ -278,10 +290,10 @@ pub async fn find_all_by_code(
         price_lowest: row.get("price_lowest"),
         price_lowest_avg: row.get("price_lowest_avg"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
-        Ok(rows) =>rows,
+        Ok(rows) => rows,
         Err(e) => {
             event!(target: "security_api", Level::ERROR, "security_price.find_all_by_code: {}", &e);
             Vec::new()
@@ -289,17 +301,12 @@ pub async fn find_all_by_code(
     }
 }
 
-pub async fn find_all_by_date(q_year: &str, q_month: &str, q_day: &str) -> Vec<SecurityPrice> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
-
-    let day = if q_day.is_empty() {
-        format!("%{0:04}/{1:02}%", q_year.parse::<i32>().unwrap() - 1911, q_month.parse::<i32>().unwrap())
-    } else if q_day.is_empty() && q_month.is_empty(){
-        format!("%{0:04}%", q_year.parse::<i32>().unwrap() - 1911)
-    } else {
-        format!("%{0:04}/{1:02}/{2:02}%", q_year.parse::<i32>().unwrap() - 1911, q_month.parse::<i32>().unwrap(), q_day.parse::<i32>().unwrap())
-    };
+pub async fn find_all_by_date(
+    repo: &Repository,
+    q_start_date: NaiveDate,
+    q_end_date: NaiveDate,
+) -> Vec<SecurityPrice> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r" 
@@ -307,9 +314,11 @@ pub async fn find_all_by_date(q_year: &str, q_month: &str, q_day: &str) -> Vec<S
              , sp.open_date_year
              , sp.open_date_month
              , sp.open_date_day
+             , sp.open_date
              , sp.security_code
              , sp.security_name
              , sp.price_date
+             , sp.trade_date
              , sp.price_close
              , sp.price_avg
              , sp.price_hight
@@ -319,19 +328,22 @@ pub async fn find_all_by_date(q_year: &str, q_month: &str, q_day: &str) -> Vec<S
              , sp.created_date
              , sp.updated_date
           FROM security_price sp
-          WHERE sp.price_date LIKE $1
-         ORDER BY sp.open_date_year, sp.open_date_month, sp.open_date_day, sp.price_date, sp.security_code
+          WHERE sp.trade_date BETWEEN $1 AND $2
+         ORDER BY sp.open_date, sp.trade_date, sp.security_code
     ",
     )
-    .bind(day)
+    .bind(q_start_date)
+    .bind(q_end_date)
     .map(|row: PgRow| SecurityPrice {
         row_id: row.get("row_id"),
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         security_code: row.get("security_code"),
         security_name: row.get("security_name"),
         price_date: row.get("price_date"),
+        trade_date: row.get("trade_date"),
         price_close: row.get("price_close"),
         price_avg: row.get("price_avg"),
         price_hight: row.get("price_hight"),
@@ -339,10 +351,10 @@ pub async fn find_all_by_date(q_year: &str, q_month: &str, q_day: &str) -> Vec<S
         price_lowest: row.get("price_lowest"),
         price_lowest_avg: row.get("price_lowest_avg"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
-        Ok(rows) =>rows,
+        Ok(rows) => rows,
         Err(e) => {
             event!(target: "security_api", Level::ERROR, "security_price.find_all_by_code: {}", &e);
             Vec::new()
@@ -350,24 +362,87 @@ pub async fn find_all_by_date(q_year: &str, q_month: &str, q_day: &str) -> Vec<S
     }
 }
 
-pub async fn find_one_by_maxdate() -> String {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_one_by_maxdate(repo: &Repository) -> NaiveDate {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r" 
-        SELECT COALESCE(MAX(concat(open_date_year, open_date_month, RIGHT(price_date, 2))), '19981231') AS price_date
+        SELECT COALESCE(MAX(sp.trade_date), DATE '1998-12-31') AS trade_date
           FROM security_price sp
-        WHERE sp.price_date not like '%月平均收盤價%'
     ",
     )
-    .fetch_one(&conn)
+    .fetch_one(conn)
     .await
     {
-        Ok(row) =>row.get("price_date"),
+        Ok(row) => row.get("trade_date"),
         Err(e) => {
             event!(target: "security_api", Level::ERROR, "security_price.find_one_by_maxdate: {}", &e);
-            String::new()
+            NaiveDate::MIN
         }
     }
 }
+
+/// 取得區間收盤價
+///
+/// # Errors
+/// 資料庫讀取失敗時回傳 `sqlx::Error`
+pub async fn find_all_by_range(
+    repo: &Repository,
+    q_security_code: &str,
+    q_start_date: Option<NaiveDate>,
+    q_end_date: Option<NaiveDate>,
+) -> Result<Vec<SecurityPrice>, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r" 
+        SELECT sp.row_id
+             , sp.open_date_year
+             , sp.open_date_month
+             , sp.open_date_day
+             , sp.open_date
+             , sp.security_code
+             , sp.security_name
+             , sp.price_date
+             , sp.trade_date
+             , sp.price_close
+             , sp.price_avg
+             , sp.price_hight
+             , sp.price_hight_avg
+             , sp.price_lowest
+             , sp.price_lowest_avg
+          FROM security_price sp
+         WHERE sp.security_code = $1
+           AND sp.trade_date IS NOT NULL
+           AND ($2::date IS NULL OR sp.trade_date >= $2)
+           AND ($3::date IS NULL OR sp.trade_date <= $3)
+         ORDER BY sp.trade_date
+    ",
+    )
+    .bind(q_security_code)
+    .bind(q_start_date)
+    .bind(q_end_date)
+    .map(|row: PgRow| SecurityPrice {
+        row_id: row.get("row_id"),
+        open_date_year: row.get("open_date_year"),
+        open_date_month: row.get("open_date_month"),
+        open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
+        security_code: row.get("security_code"),
+        security_name: row.get("security_name"),
+        price_date: row.get("price_date"),
+        trade_date: row.get("trade_date"),
+        price_close: row.get("price_close"),
+        price_avg: row.get("price_avg"),
+        price_hight: row.get("price_hight"),
+        price_hight_avg: row.get("price_hight_avg"),
+        price_lowest: row.get("price_lowest"),
+        price_lowest_avg: row.get("price_lowest_avg"),
+    })
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => Ok(rows),
+        Err(e) => Err(e),
+    }
+}
@@ -1,5 +1,6 @@
 #![warn(clippy::all, clippy::pedantic)]
 
+use chrono::NaiveDate;
 use sqlx::types::BigDecimal;
 
 #[derive(Debug, Clone)]
@@ -8,9 +9,11 @@ pub struct SecurityPrice {
     pub open_date_year: String,
     pub open_date_month: String,
     pub open_date_day: String,
+    pub open_date: NaiveDate,
     pub security_code: String,
     pub security_name: String,
     pub price_date: String,
+    pub trade_date: Option<NaiveDate>,
     pub price_close: BigDecimal,
     pub price_avg: BigDecimal,
     pub price_hight: BigDecimal,
@@ -37,31 +40,18 @@ impl std::fmt::Display for SecurityPrice {
 
         write!(
             f,
-            r#"{0}, 
-            open_date: {1}{2}{3}, 
-            security_code: {4}, 
-            security_name: {5}, 
-            price_date: {6}, 
-            price_close: {7}, 
-            price_avg: {8},
-            price_hight: {9},
-            price_hight_avg: {10},
-            price_lowest: {11},
-            price_lowest_avg: {12},
-            "#,
-            row_id,
-            open_date_year,
-            open_date_month,
-            open_date_day,
-            security_code,
-            security_name,
-            price_date,
-            price_close,
-            price_avg,
-            price_hight,
-            price_hight_avg,
-            price_lowest,
-            price_lowest_avg
+            r"{row_id}, 
+            open_date: {open_date_year}{open_date_month}{open_date_day}, 
+            security_code: {security_code}, 
+            security_name: {security_name}, 
+            price_date: {price_date}, 
+            price_close: {price_close}, 
+            price_avg: {price_avg},
+            price_hight: {price_hight},
+            price_hight_avg: {price_hight_avg},
+            price_lowest: {price_lowest},
+            price_lowest_avg: {price_lowest_avg},
+            "
         )
     }
 }
@@ -71,8 +61,10 @@ pub struct ResposePrice {
     pub open_date_year: String,
     pub open_date_month: String,
     pub open_date_day: String,
+    pub open_date: NaiveDate,
     pub security_code: String,
     pub security_name: String,
+    #[allow(dead_code)]
     pub market_type: String,
     pub data_content: String,
 }
@@ -9,17 +9,25 @@ use sqlx::PgConnection;
 use tracing::{event, Level};
 
 use crate::response_data::model::MonthlyPrice;
-use crate::{daily_task::model::DailyTask, repository::Repository, security_price::dao};
+use crate::{
+    daily_task::model::DailyTask, dry_run, job_run, repository::Repository, roc_date::RocDate,
+    security_daily_bar, security_price::dao, Error,
+};
 
 use super::model::{ResposePrice, SecurityPrice};
 
-pub async fn get_security_to_price(task: &DailyTask) -> Result<(), sqlx::Error> {
+/// 回應資料轉收盤價 (試跑時僅列出將取代或刪除的價格)
+pub async fn get_security_to_price(
+    repo: &Repository,
+    task: &DailyTask,
+    dry_run: bool,
+) -> Result<(), Error> {
     event!(target: "security_api", Level::DEBUG, "call daily_task.get_security_to_price");
 
     let q_year = &task.open_date_year;
     let q_month = &task.open_date_month;
 
-    let res_prices = dao::find_all_by_res(q_year, q_month).await;
+    let res_prices = dao::find_all_by_res(repo, q_year, q_month).await;
     for price in res_prices {
         event!(target: "security_api", Level::DEBUG, "ResposePrice: {:?}", &price);
 
@@ -27,70 +35,98 @@ pub async fn get_security_to_price(task: &DailyTask) -> Result<(), sqlx::Error>
         let q_month = &price.open_date_month;
         let q_security_code = &price.security_code;
 
-        let month_prices =
-            dao::find_all(q_year, q_month, q_security_code).await;
-        if month_prices.len() <= 0 {
-            loop_data_res(&price, &Vec::new()).await?;
-        } else {
-            let price_dates: Vec<(String, BigDecimal)> = month_prices.iter().map(|x| (x.price_date.clone(), x.price_close.clone())).collect();
-            loop_data_res(&price, &price_dates).await?;
-        }
+        let month_prices = dao::find_all(repo, q_year, q_month, q_security_code).await;
+        let price_dates: Vec<(String, BigDecimal)> = month_prices
+            .iter()
+            .map(|x| (x.price_date.clone(), x.price_close.clone()))
+            .collect();
+        loop_data_res(repo, &price, &price_dates, dry_run).await?;
     }
 
     Ok(())
 }
 
-async fn loop_data_res(data: &ResposePrice, price_dates: &Vec<(String, BigDecimal)>) -> Result<(), sqlx::Error> {
+async fn loop_data_res(
+    repo: &Repository,
+    data: &ResposePrice,
+    price_dates: &[(String, BigDecimal)],
+    dry_run: bool,
+) -> Result<(), Error> {
     let data_content = &data.data_content;
 
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+    let conn = &repo.connection;
 
     match serde_json::from_str::<MonthlyPrice>(data_content) {
         Ok(data_row) => {
+            if !data_row.bars.is_empty() && !dry_run {
+                let mut trax_conn = conn.begin().await?;
+                security_daily_bar::service::save_bars(&mut trax_conn, &data_row.bars).await?;
+                trax_conn.commit().await?;
+            }
             if !data_row.data.is_empty() {
                 for row in data_row.data {
-                    let mut trax_conn = conn.begin().await?;
+                    let price_date = RocDate::from_str(&row[0])?;
+                    let price_close = BigDecimal::from_str(&row[1])
+                        .map_err(|e| Error::Parse(format!("price_close {}: {e}", &row[1])))?;
 
-                    let price_date = row[0].trim().to_string();
-                    let price_close = BigDecimal::from_str(&row[1]).unwrap();
-
-                    let new_price_date = format!("{:0>10}", price_date);
-                    if price_dates.contains(&(new_price_date.clone(), price_close.clone())) {
+                    if price_dates.contains(&(price_date.price_date(), price_close.clone())) {
+                        continue;
+                    }
+                    if dry_run {
+                        plan_price(data, price_date, &price_close, price_dates);
                         continue;
                     }
 
-                    match loop_data_price(&mut trax_conn, &new_price_date, &price_close, data)
-                        .await
-                    {
-                        Ok(_) => {
+                    let mut trax_conn = conn.begin().await?;
+
+                    match loop_data_price(&mut trax_conn, price_date, &price_close, data).await {
+                        Ok(()) => {
                             trax_conn.commit().await?;
                         }
                         Err(e) => {
                             trax_conn.rollback().await?;
-                            return Err(e.into());
+                            return Err(e);
                         }
                     }
                 }
             }
         }
-        Err(e) => return Err(sqlx::Error::Decode(Box::new(e))),
+        Err(e) => return Err(e.into()),
     }
     Ok(())
 }
 
+/// 試跑：列出同日舊價格 (將刪除) 與新價格 (將新增，無成交時不新增)
+fn plan_price(
+    data: &ResposePrice,
+    price_date: RocDate,
+    price_close: &BigDecimal,
+    price_dates: &[(String, BigDecimal)],
+) {
+    let price_date = price_date.price_date();
+    let plan_row = |close: &BigDecimal| format!("{} {} {}", data.security_code, price_date, close);
+
+    if let Some((_, old_close)) = price_dates.iter().find(|(date, _)| *date == price_date) {
+        dry_run::delete("security_price", &plan_row(old_close));
+    }
+    if *price_close > BigDecimal::zero() {
+        dry_run::insert("security_price", &plan_row(price_close));
+    }
+}
+
 async fn loop_data_price(
     trax_conn: &mut PgConnection,
-    price_date: &str,
+    price_date: RocDate,
     price_close: &BigDecimal,
     data: &ResposePrice,
-) -> Result<(), sqlx::Error> {
-
+) -> Result<(), Error> {
     let price = SecurityPrice {
         open_date_year: data.open_date_year.clone(),
         open_date_month: data.open_date_month.clone(),
         open_date_day: data.open_date_day.clone(),
-        price_date: price_date.to_owned(),
+        open_date: data.open_date,
+        price_date: price_date.price_date(),
+        trade_date: Some(price_date.to_naive()),
         security_code: data.security_code.clone(),
         security_name: data.security_name.clone(),
         price_close: price_close.clone(),
@@ -99,50 +135,56 @@ async fn loop_data_price(
         price_hight_avg: BigDecimal::zero(),
         price_lowest: BigDecimal::zero(),
         price_lowest_avg: BigDecimal::zero(),
-        row_id: "".to_string(),
+        row_id: String::new(),
     };
 
     dao::remove(trax_conn, price.clone()).await?;
     if *price_close > BigDecimal::zero() {
-        dao::create(trax_conn, price.clone()).await?;
+        let cnt = dao::create(trax_conn, price.clone()).await?;
+        job_run::service::count_inserted("security_price", cnt);
+    } else {
+        job_run::service::count_skipped(1);
     }
 
     Ok(())
 }
 
-pub async fn get_calculator_to_price(task: &DailyTask) -> Result<(), sqlx::Error> {
+/// 計算平均價格 (試跑時僅列出將更新的價格)
+pub async fn get_calculator_to_price(
+    repo: &Repository,
+    task: &DailyTask,
+    dry_run: bool,
+) -> Result<(), Error> {
     event!(target: "security_api", Level::INFO, "call daily_task.get_calculator_to_price");
 
-    let q_year = &task.open_date_year;
-    let q_month = &task.open_date_month;
-    let q_day = &task.open_date_day;
-
-    let res_prices = dao::find_all_by_date(q_year, q_month, q_day).await;
+    let res_prices = dao::find_all_by_date(repo, task.open_date, task.open_date).await;
     for price in res_prices {
         event!(target: "security_api", Level::DEBUG, "SecurityPrice: {:?}", &price);
-        loop_data_calculator(&price).await?;
+        loop_data_calculator(repo, &price, dry_run).await?;
     }
 
     Ok(())
 }
 
-async fn loop_data_calculator(data: &SecurityPrice) -> Result<(), sqlx::Error> {
-    let q_year = &data.open_date_year;
-    let q_month = &data.open_date_month;
-    let q_day = &data.open_date_day;
-    let q_open_date = &format!("{0}{1}{2}", q_year, q_month, q_day);
+async fn loop_data_calculator(
+    repo: &Repository,
+    data: &SecurityPrice,
+    dry_run: bool,
+) -> Result<(), Error> {
+    let Some(q_trade_date) = data.trade_date else {
+        return Ok(());
+    };
     let q_security_code = &data.security_code;
-    let q_price_date = &data.price_date;
 
-    let resp_prices = dao::find_all_by_code(
-        q_open_date,
-        q_price_date,
-        q_security_code,
-    )
-    .await;
+    let resp_prices =
+        dao::find_all_by_code(repo, data.open_date, q_trade_date, q_security_code).await;
 
-    let price_avg =
-        get_calculator_avg(&resp_prices.iter().map(|x| x.price_close.clone()).collect());
+    let price_avg = get_calculator_avg(
+        &resp_prices
+            .iter()
+            .map(|x| x.price_close.clone())
+            .collect::<Vec<_>>(),
+    );
 
     let (price_max, price_avg_max) = get_calculator_max_avg(&price_avg, &resp_prices);
     let (price_min, price_avg_min) = get_calculator_min_avg(&price_avg, &resp_prices);
@@ -156,11 +198,39 @@ async fn loop_data_calculator(data: &SecurityPrice) -> Result<(), sqlx::Error> {
         &price_avg_min,
     );
 
-    dao::modify(new_price).await?;
+    if dry_run {
+        plan_calculator(data, &new_price);
+        return Ok(());
+    }
+
+    let cnt = dao::modify(repo, new_price).await?;
+    job_run::service::count_updated("security_price", cnt);
 
     Ok(())
 }
 
+/// 試跑：列出將更新的平均價格 (舊值將刪除、新值將新增，未變動時不列出)
+fn plan_calculator(old_price: &SecurityPrice, new_price: &SecurityPrice) {
+    let plan_row = |x: &SecurityPrice| {
+        format!(
+            "{} {} {} {} {} {} {}",
+            x.security_code,
+            x.price_date,
+            x.price_avg,
+            x.price_hight,
+            x.price_hight_avg,
+            x.price_lowest,
+            x.price_lowest_avg
+        )
+    };
+
+    let (old_row, new_row) = (plan_row(old_price), plan_row(new_price));
+    if old_row != new_row {
+        dry_run::delete("security_price", &old_row);
+        dry_run::insert("security_price", &new_row);
+    }
+}
+
 fn get_security_price(
     price: &SecurityPrice,
     price_avg: &BigDecimal,
@@ -179,7 +249,7 @@ fn get_security_price(
     new_price
 }
 
-fn get_calculator_avg(resp_prices: &Vec<BigDecimal>) -> BigDecimal {
+fn get_calculator_avg(resp_prices: &[BigDecimal]) -> BigDecimal {
     let mut sum_count = BigDecimal::from(0);
     let mut sum_price = BigDecimal::from(0);
 
@@ -193,7 +263,7 @@ fn get_calculator_avg(resp_prices: &Vec<BigDecimal>) -> BigDecimal {
 
 fn get_calculator_max_avg(
     price_avg: &BigDecimal,
-    resp_prices: &Vec<SecurityPrice>,
+    resp_prices: &[SecurityPrice],
 ) -> (BigDecimal, BigDecimal) {
     let price_list = resp_prices
         .iter()
@@ -209,7 +279,7 @@ fn get_calculator_max_avg(
 
 fn get_calculator_min_avg(
     price_avg: &BigDecimal,
-    resp_prices: &Vec<SecurityPrice>,
+    resp_prices: &[SecurityPrice],
 ) -> (BigDecimal, BigDecimal) {
     let price_list = resp_prices
         .iter()
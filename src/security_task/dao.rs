@@ -1,6 +1,6 @@
 #![warn(clippy::all, clippy::pedantic)]
 
-use chrono::Local;
+use chrono::{DateTime, Local, NaiveDate};
 use sqlx::{postgres::PgRow, Row};
 use tracing::{event, Level};
 
@@ -8,9 +8,8 @@ use crate::{daily_task::model::DailyTask, repository::Repository};
 
 use super::model::SecurityTask;
 
-pub async fn create(data: SecurityTask) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn create(repo: &Repository, data: SecurityTask) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -18,6 +17,7 @@ pub async fn create(data: SecurityTask) -> Result<u64, sqlx::Error> {
             open_date_year
           , open_date_month
           , open_date_day
+          , open_date
           , security_code 
           , security_name 
           , market_type 
@@ -26,15 +26,19 @@ pub async fn create(data: SecurityTask) -> Result<u64, sqlx::Error> {
           , exec_count 
           , is_enabled 
           , sort_no 
+          , exec_status
+          , retry_count
+          , last_error
           , created_date
           , updated_date
         ) VALUES ( $1, $2, $3, $4, $5, $6, $7, 
-         $8, $9, $10, $11, $12, $13 )
+         $8, $9, $10, $11, $12, $13, $14, $15, $16, $17 )
     ",
     )
     .bind(data.open_date_year)
     .bind(data.open_date_month)
     .bind(data.open_date_day)
+    .bind(data.open_date)
     .bind(data.security_code)
     .bind(data.security_name)
     .bind(data.market_type)
@@ -43,9 +47,12 @@ pub async fn create(data: SecurityTask) -> Result<u64, sqlx::Error> {
     .bind(data.exec_count)
     .bind(data.is_enabled)
     .bind(data.sort_no)
+    .bind(data.exec_status)
+    .bind(data.retry_count)
+    .bind(data.last_error)
     .bind(Local::now())
     .bind(Local::now())
-    .execute(&conn)
+    .execute(conn)
     .await
     {
         Ok(cnt) => Ok(cnt.rows_affected()),
@@ -53,9 +60,8 @@ pub async fn create(data: SecurityTask) -> Result<u64, sqlx::Error> {
     }
 }
 
-pub async fn modify(data: SecurityTask) -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn modify(repo: &Repository, data: SecurityTask) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -63,21 +69,26 @@ pub async fn modify(data: SecurityTask) -> Result<u64, sqlx::Error> {
            SET open_date_year = $1
              , open_date_month = $2
              , open_date_day = $3
-             , security_code = $4
-             , security_name = $5
-             , market_type = $6
-             , issue_date = $7
-             , exec_seed = $8
-             , exec_count = $9
-             , is_enabled = $10
-             , sort_no = $11
-             , updated_date = $12
-         WHERE row_id = $13
+             , open_date = $4
+             , security_code = $5
+             , security_name = $6
+             , market_type = $7
+             , issue_date = $8
+             , exec_seed = $9
+             , exec_count = $10
+             , is_enabled = $11
+             , sort_no = $12
+             , exec_status = $13
+             , retry_count = $14
+             , last_error = $15
+             , updated_date = $16
+         WHERE row_id = $17
     ",
     )
     .bind(data.open_date_year)
     .bind(data.open_date_month)
     .bind(data.open_date_day)
+    .bind(data.open_date)
     .bind(data.security_code)
     .bind(data.security_name)
     .bind(data.market_type)
@@ -86,9 +97,12 @@ pub async fn modify(data: SecurityTask) -> Result<u64, sqlx::Error> {
     .bind(data.exec_count)
     .bind(data.is_enabled)
     .bind(data.sort_no)
+    .bind(data.exec_status)
+    .bind(data.retry_count)
+    .bind(data.last_error)
     .bind(Local::now())
     .bind(data.row_id)
-    .execute(&conn)
+    .execute(conn)
     .await
     {
         Ok(cnt) => Ok(cnt.rows_affected()),
@@ -97,6 +111,7 @@ pub async fn modify(data: SecurityTask) -> Result<u64, sqlx::Error> {
 }
 
 pub async fn find_one(
+    repo: &Repository,
     q_year: &str,
     q_month: &str,
     q_day: &str,
@@ -104,8 +119,7 @@ pub async fn find_one(
     q_market_type: &str,
     q_issue_date: &str,
 ) -> Option<SecurityTask> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -113,6 +127,7 @@ pub async fn find_one(
              , open_date_year
              , open_date_month
              , open_date_day
+             , open_date
              , security_code 
              , security_name 
              , market_type 
@@ -121,6 +136,9 @@ pub async fn find_one(
              , exec_count 
              , is_enabled 
              , sort_no 
+             , exec_status
+             , retry_count
+             , last_error
              , created_date
              , updated_date
           FROM security_task
@@ -143,6 +161,7 @@ pub async fn find_one(
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         security_code: row.get("security_code"),
         security_name: row.get("security_name"),
         market_type: row.get("market_type"),
@@ -151,8 +170,11 @@ pub async fn find_one(
         exec_count: row.get("exec_count"),
         is_enabled: row.get("is_enabled"),
         sort_no: row.get("sort_no"),
+        exec_status: row.get("exec_status"),
+        retry_count: row.get("retry_count"),
+        last_error: row.get("last_error"),
     })
-    .fetch_optional(&conn)
+    .fetch_optional(conn)
     .await
     {
         Ok(row) => row,
@@ -163,9 +185,8 @@ pub async fn find_one(
     }
 }
 
-pub async fn find_all_by_twse(task: &DailyTask) -> Vec<SecurityTask> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_all_by_twse(repo: &Repository, task: &DailyTask) -> Vec<SecurityTask> {
+    let conn = &repo.connection;
 
     let q_year = task.clone().open_date_year;
     let q_month = task.clone().open_date_month;
@@ -177,6 +198,7 @@ pub async fn find_all_by_twse(task: &DailyTask) -> Vec<SecurityTask> {
              , open_date_year
              , open_date_month
              , open_date_day
+             , open_date
              , security_code
              , security_name
              , market_type
@@ -185,6 +207,9 @@ pub async fn find_all_by_twse(task: &DailyTask) -> Vec<SecurityTask> {
              , exec_count
              , is_enabled
              , sort_no
+             , exec_status
+             , retry_count
+             , last_error
           FROM security_task 
          WHERE open_date_year = $1
            AND open_date_month = $2
@@ -201,6 +226,7 @@ pub async fn find_all_by_twse(task: &DailyTask) -> Vec<SecurityTask> {
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         security_code: row.get("security_code"),
         security_name: row.get("security_name"),
         market_type: row.get("market_type"),
@@ -209,8 +235,11 @@ pub async fn find_all_by_twse(task: &DailyTask) -> Vec<SecurityTask> {
         exec_count: row.get("exec_count"),
         is_enabled: row.get("is_enabled"),
         sort_no: row.get("sort_no"),
+        exec_status: row.get("exec_status"),
+        retry_count: row.get("retry_count"),
+        last_error: row.get("last_error"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
         Ok(rows) => rows,
@@ -221,9 +250,8 @@ pub async fn find_all_by_twse(task: &DailyTask) -> Vec<SecurityTask> {
     }
 }
 
-pub async fn find_all_by_tpex(task: &DailyTask) -> Vec<SecurityTask> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_all_by_tpex(repo: &Repository, task: &DailyTask) -> Vec<SecurityTask> {
+    let conn = &repo.connection;
 
     let q_year = task.clone().open_date_year;
     let q_month = task.clone().open_date_month;
@@ -235,6 +263,7 @@ pub async fn find_all_by_tpex(task: &DailyTask) -> Vec<SecurityTask> {
              , open_date_year
              , open_date_month
              , open_date_day
+             , open_date
              , security_code
              , security_name
              , market_type
@@ -243,6 +272,9 @@ pub async fn find_all_by_tpex(task: &DailyTask) -> Vec<SecurityTask> {
              , exec_count
              , is_enabled
              , sort_no
+             , exec_status
+             , retry_count
+             , last_error
           FROM security_task 
          WHERE open_date_year = $1
            AND open_date_month = $2
@@ -259,6 +291,7 @@ pub async fn find_all_by_tpex(task: &DailyTask) -> Vec<SecurityTask> {
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         security_code: row.get("security_code"),
         security_name: row.get("security_name"),
         market_type: row.get("market_type"),
@@ -267,8 +300,11 @@ pub async fn find_all_by_tpex(task: &DailyTask) -> Vec<SecurityTask> {
         exec_count: row.get("exec_count"),
         is_enabled: row.get("is_enabled"),
         sort_no: row.get("sort_no"),
+        exec_status: row.get("exec_status"),
+        retry_count: row.get("retry_count"),
+        last_error: row.get("last_error"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
         Ok(rows) => rows,
@@ -279,13 +315,243 @@ pub async fn find_all_by_tpex(task: &DailyTask) -> Vec<SecurityTask> {
     }
 }
 
-pub async fn find_all_by_times(
-    q_year: &str,
-    q_month: &str,
-    q_day: &str,
+/// 認領待執行任務 (其他程序認領中或本次執行後已處理者略過)
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn modify_by_claim(
+    repo: &Repository,
+    q_worker: &str,
+    q_started: DateTime<Local>,
+    q_open_date: Option<NaiveDate>,
+    q_market_types: &[String],
+    q_batch_size: i64,
+    q_max_exec_count: i32,
+) -> Result<Vec<SecurityTask>, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        UPDATE security_task 
+           SET claimed_by = $1
+             , claimed_at = now()
+             , updated_date = now()
+         WHERE row_id IN (
+               SELECT row_id
+                 FROM security_task
+                WHERE ($3::date IS NULL OR open_date = $3)
+                  AND exec_count <= $6
+                  AND is_enabled = 1
+                  AND exec_status <> 'FAILED'
+                  AND claimed_by = ''
+                  AND (claimed_at IS NULL OR claimed_at < $2)
+                  AND (cardinality($4::varchar[]) = 0 OR market_type = ANY($4))
+                ORDER BY open_date, sort_no
+                LIMIT $5
+                  FOR UPDATE SKIP LOCKED
+            )
+        RETURNING row_id
+                , open_date_year
+                , open_date_month
+                , open_date_day
+                , open_date
+                , security_code
+                , security_name
+                , market_type
+                , issue_date
+                , exec_seed
+                , exec_count
+                , is_enabled
+                , sort_no
+                , exec_status
+                , retry_count
+                , last_error
+          ",
+    )
+    .bind(q_worker)
+    .bind(q_started)
+    .bind(q_open_date)
+    .bind(q_market_types)
+    .bind(q_batch_size)
+    .bind(q_max_exec_count)
+    .map(|row: PgRow| SecurityTask {
+        row_id: row.get("row_id"),
+        open_date_year: row.get("open_date_year"),
+        open_date_month: row.get("open_date_month"),
+        open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
+        security_code: row.get("security_code"),
+        security_name: row.get("security_name"),
+        market_type: row.get("market_type"),
+        issue_date: row.get("issue_date"),
+        exec_seed: row.get("exec_seed"),
+        exec_count: row.get("exec_count"),
+        is_enabled: row.get("is_enabled"),
+        sort_no: row.get("sort_no"),
+        exec_status: row.get("exec_status"),
+        retry_count: row.get("retry_count"),
+        last_error: row.get("last_error"),
+    })
+    .fetch_all(conn)
+    .await
+    {
+        Ok(mut rows) => {
+            rows.sort_by_key(|x| (x.open_date, x.sort_no));
+            Ok(rows)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 釋放認領 (紀錄處理結束時間)
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn modify_by_release(
+    repo: &Repository,
+    q_row_id: &str,
+    q_worker: &str,
+) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        UPDATE security_task 
+           SET claimed_by = ''
+             , claimed_at = now()
+             , updated_date = now()
+         WHERE row_id = $1
+           AND claimed_by = $2
+    ",
+    )
+    .bind(q_row_id)
+    .bind(q_worker)
+    .execute(conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
+    }
+}
+
+/// 更新認領時間 (處理中的任務不被回收)
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn modify_by_heartbeat(repo: &Repository, q_worker: &str) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        UPDATE security_task 
+           SET claimed_at = now()
+         WHERE claimed_by = $1
+    ",
+    )
+    .bind(q_worker)
+    .execute(conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
+    }
+}
+
+/// 回收逾時未釋放的認領 (程序中斷)
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn modify_by_reaper(
+    repo: &Repository,
+    q_timeout_seconds: i32,
+) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        UPDATE security_task 
+           SET claimed_by = ''
+             , claimed_at = NULL
+             , updated_date = now()
+         WHERE claimed_by <> ''
+           AND claimed_at < now() - make_interval(secs => $1)
+    ",
+    )
+    .bind(q_timeout_seconds)
+    .execute(conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
+    }
+}
+
+/// 取得各證券最新任務
+///
+/// # Errors
+/// 資料庫讀取失敗時回傳 `sqlx::Error`
+pub async fn find_all_by_latest(
+    repo: &Repository,
+    q_market_type: Option<&str>,
+) -> Result<Vec<SecurityTask>, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        SELECT DISTINCT ON (security_code)
+               row_id
+             , open_date_year
+             , open_date_month
+             , open_date_day
+             , open_date
+             , security_code
+             , security_name
+             , market_type
+             , issue_date
+             , exec_seed
+             , exec_count
+             , is_enabled
+             , sort_no
+             , exec_status
+             , retry_count
+             , last_error
+          FROM security_task 
+         WHERE ($1::varchar IS NULL OR market_type = $1)
+         ORDER BY security_code, open_date_year desc, open_date_month desc, open_date_day desc
+          ",
+    )
+    .bind(q_market_type)
+    .map(|row: PgRow| SecurityTask {
+        row_id: row.get("row_id"),
+        open_date_year: row.get("open_date_year"),
+        open_date_month: row.get("open_date_month"),
+        open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
+        security_code: row.get("security_code"),
+        security_name: row.get("security_name"),
+        market_type: row.get("market_type"),
+        issue_date: row.get("issue_date"),
+        exec_seed: row.get("exec_seed"),
+        exec_count: row.get("exec_count"),
+        is_enabled: row.get("is_enabled"),
+        sort_no: row.get("sort_no"),
+        exec_status: row.get("exec_status"),
+        retry_count: row.get("retry_count"),
+        last_error: row.get("last_error"),
+    })
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => Ok(rows),
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn find_all_by_failed(
+    repo: &Repository,
+    q_security_codes: &[String],
 ) -> Vec<SecurityTask> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -293,6 +559,7 @@ pub async fn find_all_by_times(
              , open_date_year
              , open_date_month
              , open_date_day
+             , open_date
              , security_code
              , security_name
              , market_type
@@ -301,23 +568,22 @@ pub async fn find_all_by_times(
              , exec_count
              , is_enabled
              , sort_no
+             , exec_status
+             , retry_count
+             , last_error
           FROM security_task 
-         WHERE open_date_year = $1
-           AND open_date_month = $2
-           AND open_date_day = $3
-           AND exec_count <= 3
-           AND is_enabled = 1
-         ORDER BY sort_no
+         WHERE exec_status = 'FAILED'
+           AND (cardinality($1::varchar[]) = 0 OR security_code = ANY($1))
+         ORDER BY open_date, sort_no
           ",
     )
-    .bind(q_year)
-    .bind(q_month)
-    .bind(q_day)
+    .bind(q_security_codes)
     .map(|row: PgRow| SecurityTask {
         row_id: row.get("row_id"),
         open_date_year: row.get("open_date_year"),
         open_date_month: row.get("open_date_month"),
         open_date_day: row.get("open_date_day"),
+        open_date: row.get("open_date"),
         security_code: row.get("security_code"),
         security_name: row.get("security_name"),
         market_type: row.get("market_type"),
@@ -326,13 +592,44 @@ pub async fn find_all_by_times(
         exec_count: row.get("exec_count"),
         is_enabled: row.get("is_enabled"),
         sort_no: row.get("sort_no"),
+        exec_status: row.get("exec_status"),
+        retry_count: row.get("retry_count"),
+        last_error: row.get("last_error"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "security_task.find_all_by_failed: {}", &e);
+            Vec::new()
+        }
+    }
+}
+
+/// 各市場待執行任務數
+pub async fn find_all_by_backlog(repo: &Repository, q_max_exec_count: i32) -> Vec<(String, i64)> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        SELECT market_type
+             , count(*) AS backlog
+          FROM security_task 
+         WHERE exec_count <= $1
+           AND is_enabled = 1
+           AND exec_status <> 'FAILED'
+         GROUP BY market_type
+    ",
+    )
+    .bind(q_max_exec_count)
+    .map(|row: PgRow| (row.get("market_type"), row.get("backlog")))
+    .fetch_all(conn)
     .await
     {
         Ok(rows) => rows,
         Err(e) => {
-            event!(target: "security_api", Level::ERROR, "security_task.find_all_by_times: {}", &e);
+            event!(target: "security_api", Level::ERROR, "security_task.find_all_by_backlog: {}", &e);
             Vec::new()
         }
     }
@@ -1,11 +1,14 @@
 #![warn(clippy::all, clippy::pedantic)]
 
+use chrono::NaiveDate;
+
 #[derive(Debug, Clone)]
 pub struct SecurityTask {
     pub row_id: String,
     pub open_date_year: String,
     pub open_date_month: String,
     pub open_date_day: String,
+    pub open_date: NaiveDate,
     pub security_code: String,
     pub security_name: String,
     pub market_type: String,
@@ -14,6 +17,9 @@ pub struct SecurityTask {
     pub exec_count: i32,
     pub is_enabled: i32,
     pub sort_no: i32,
+    pub exec_status: String,
+    pub retry_count: i32,
+    pub last_error: String,
 }
 
 impl std::fmt::Display for SecurityTask {
@@ -30,31 +36,25 @@ impl std::fmt::Display for SecurityTask {
         let exec_count = self.exec_count;
         let is_enabled = self.is_enabled;
         let sort_no = self.sort_no;
+        let exec_status = self.exec_status.clone();
+        let retry_count = self.retry_count;
+        let last_error = self.last_error.clone();
         write!(
             f,
-            r#"{0}, 
-            open_date: {1}{2}{3}, 
-            security_code: {4}, 
-            security_name: {5}, 
-            market_type: {6}, 
-            issue_date: {7}, 
-            exec_seed: {8}, 
-            exec_count: {9}, 
-            is_enabled: {10}, 
-            sort_no: {11}
-            "#,
-            row_id,
-            open_date_year,
-            open_date_month,
-            open_date_day,
-            security_code,
-            security_name,
-            market_type,
-            issue_date,
-            exec_seed,
-            exec_count,
-            is_enabled,
-            sort_no
+            r"{row_id}, 
+            open_date: {open_date_year}{open_date_month}{open_date_day}, 
+            security_code: {security_code}, 
+            security_name: {security_name}, 
+            market_type: {market_type}, 
+            issue_date: {issue_date}, 
+            exec_seed: {exec_seed}, 
+            exec_count: {exec_count}, 
+            is_enabled: {is_enabled}, 
+            sort_no: {sort_no}, 
+            exec_status: {exec_status}, 
+            retry_count: {retry_count}, 
+            last_error: {last_error}
+            "
         )
     }
 }
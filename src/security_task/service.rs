@@ -1,50 +1,71 @@
 #![warn(clippy::all, clippy::pedantic)]
 
-use std::{cmp::max, time::Duration};
+use std::{cmp::max, collections::HashMap, sync::Arc, time::Duration};
 
 use chrono::{Local, NaiveDate};
 use rand::{rng, Rng};
-use tokio::time::{self, sleep};
-use tokio_retry::{strategy::ExponentialBackoff, Retry};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_retry::Retry;
 use tracing::{event, Level};
 
 use super::{dao, model::SecurityTask};
 use crate::{
+    config,
     daily_task::model::DailyTask,
-    response_data::{self, model::ResponseData},
+    dry_run, job_run, listen_flow,
+    market_source::{self, limiter::MarketLimiter},
+    metrics,
+    repository::Repository,
+    response_data::{
+        self,
+        model::{FetchOutcome, MonthlyPrice, ResponseData},
+    },
     security_temp::{self, model::SecurityTemp},
+    throttle_event, Error,
 };
 
-/// 新增任務資料
-pub async fn insert_task_data(task: &DailyTask) -> Result<(), sqlx::Error> {
+/// 每批認領的任務數
+pub const CLAIM_BATCH_SIZE: i64 = 20;
+
+/// 新增任務資料 (試跑時僅列出將新增的任務)
+pub async fn insert_task_data(
+    repo: &Repository,
+    task: &DailyTask,
+    dry_run: bool,
+) -> Result<(), Error> {
     event!(target: "security_api", Level::INFO, "call daily_task.temp_to_task");
 
     let mut security_tasks = Vec::<SecurityTask>::new();
 
-    let twse_list = security_temp::dao::find_all_by_twse(&task).await;
-    let tpex_list = security_temp::dao::find_all_by_tpex(&task).await;
+    let twse_list = security_temp::dao::find_all_by_twse(repo, task).await;
+    let tpex_list = security_temp::dao::find_all_by_tpex(repo, task).await;
 
     let max_count = max(twse_list.len(), tpex_list.len());
 
     let mut sort_num = 0;
     for i in 0..max_count {
         if i < twse_list.len() {
-            sort_num = sort_num + 1;
+            sort_num += 1;
 
             let twse_data = &twse_list[i];
-            security_tasks.push(get_new_security_task(twse_data, &task, sort_num));
+            security_tasks.push(get_new_security_task(twse_data, task, sort_num));
         }
         if i < tpex_list.len() {
-            sort_num = sort_num + 1;
+            sort_num += 1;
 
             let tpex_data = &tpex_list[i];
-            security_tasks.push(get_new_security_task(tpex_data, &task, sort_num));
+            security_tasks.push(get_new_security_task(tpex_data, task, sort_num));
         }
     }
 
     for security_task in security_tasks {
-        if !check_data_exists(&security_task).await {
-            dao::create(security_task).await?;
+        if check_data_exists(repo, &security_task).await {
+            job_run::service::count_skipped(1);
+        } else if dry_run {
+            dry_run::insert("security_task", &plan_row(&security_task));
+        } else {
+            let cnt = dao::create(repo, security_task).await?;
+            job_run::service::count_inserted("security_task", cnt);
         }
     }
 
@@ -53,8 +74,8 @@ pub async fn insert_task_data(task: &DailyTask) -> Result<(), sqlx::Error> {
 
 /// 取得新任務資料
 fn get_new_security_task(data: &SecurityTemp, task: &DailyTask, item_index: i32) -> SecurityTask {
-    let seed: i64 = rng().random_range(1..=9999999999999);
-    let security_seed = format!("{:013}", seed);
+    let seed: i64 = rng().random_range(1..=9_999_999_999_999);
+    let security_seed = format!("{seed:013}");
     let sort_no = item_index;
 
     SecurityTask {
@@ -65,16 +86,28 @@ fn get_new_security_task(data: &SecurityTemp, task: &DailyTask, item_index: i32)
         exec_count: 0,
         is_enabled: 1,
         sort_no,
+        exec_status: "WAIT".to_string(),
+        retry_count: 0,
+        last_error: String::new(),
         open_date_year: task.open_date_year.clone(),
         open_date_month: task.open_date_month.clone(),
         open_date_day: task.open_date_day.clone(),
+        open_date: task.open_date,
         exec_seed: security_seed,
-        row_id: "".to_string(),
+        row_id: String::new(),
     }
 }
 
+/// 試跑報告的任務內容
+pub fn plan_row(task: &SecurityTask) -> String {
+    format!(
+        "{} {} {} {} sort_no {}",
+        task.open_date, task.market_type, task.security_code, task.security_name, task.sort_no
+    )
+}
+
 /// 檢查資料是否存在
-async fn check_data_exists(task: &SecurityTask) -> bool {
+async fn check_data_exists(repo: &Repository, task: &SecurityTask) -> bool {
     let q_year = &task.open_date_year;
     let q_month = &task.open_date_month;
     let q_day = &task.open_date_day;
@@ -83,6 +116,7 @@ async fn check_data_exists(task: &SecurityTask) -> bool {
     let q_issue_date = &task.issue_date;
 
     dao::find_one(
+        repo,
         q_year,
         q_month,
         q_day,
@@ -94,243 +128,332 @@ async fn check_data_exists(task: &SecurityTask) -> bool {
     .is_some()
 }
 
-/// 取得所有任務資料
-pub async fn get_all_task(task: &DailyTask) -> Result<(), Box<dyn std::error::Error>> {
+/// 取得所有任務資料 (各主機平行查詢，同主機依排序)
+pub async fn get_all_task(
+    repo: &Repository,
+    task: &DailyTask,
+    market_types: &[String],
+) -> Result<(), Error> {
     event!(target: "security_api", Level::INFO, "call daily_task.task_run");
 
-    let q_year = &task.open_date_year;
-    let q_month = &task.open_date_month;
-    let q_day = &task.open_date_day;
+    drain_task(repo, Some(task.open_date), market_types, CLAIM_BATCH_SIZE).await
+}
 
-    let securitys = dao::find_all_by_times(q_year, q_month, q_day).await;
+/// 分批認領並執行任務，直到沒有可認領的任務 (可多個程序同時執行)
+pub async fn drain_task(
+    repo: &Repository,
+    open_date: Option<NaiveDate>,
+    market_types: &[String],
+    batch_size: i64,
+) -> Result<(), Error> {
+    let worker = listen_flow::service::current_owner();
+    let started = Local::now();
+    let limiters = market_source::service::build_limiters();
+    let task_config = &config::service::get().task;
+
+    loop {
+        let reaped = dao::modify_by_reaper(repo, task_config.claim_timeout_secs).await?;
+        if reaped > 0 {
+            event!(target: "security_api", Level::WARN, "security_task.reaper released {} abandoned claims", reaped);
+        }
 
-    let mut old_market_type = "";
+        let securitys = dao::modify_by_claim(
+            repo,
+            worker,
+            started,
+            open_date,
+            market_types,
+            batch_size,
+            task_config.max_exec_count,
+        )
+        .await?;
+        if securitys.is_empty() {
+            break;
+        }
+        event!(target: "security_api", Level::INFO, "security_task.claim {} {}", worker, securitys.len());
 
-    let mut index = 0;
-    while securitys.len() > index {
-        let security = &securitys[index];
+        let mut host_tasks = HashMap::<&'static str, Vec<SecurityTask>>::new();
+        for security in securitys {
+            let Some(source) = market_source::service::find_source(&security.market_type) else {
+                release_data(repo, &security).await;
+                continue;
+            };
+            host_tasks.entry(source.host()).or_default().push(security);
+        }
+
+        let heartbeat = start_heartbeat(repo);
+        let mut markets = JoinSet::new();
+        for (host, securitys) in host_tasks {
+            let repo = repo.clone();
+            let limiter = Arc::clone(&limiters[host]);
+            let counter = job_run::service::current_counter();
+            markets.spawn(job_run::service::scope(counter, async move {
+                loop_data_market(&repo, &limiter, securitys).await;
+            }));
+        }
+
+        while let Some(result) = markets.join_next().await {
+            if let Err(e) = result {
+                event!(target: "security_api", Level::ERROR, "daily_task.get_all_task {}", &e);
+            }
+        }
+        heartbeat.abort();
+    }
+
+    Ok(())
+}
+
+/// 定期更新本程序認領中任務的認領時間 (退避等待期間不被回收)
+fn start_heartbeat(repo: &Repository) -> JoinHandle<()> {
+    let repo = repo.clone();
+    let timeout_secs = config::service::get().task.claim_timeout_secs;
+    let period = Duration::from_secs(max(u64::try_from(timeout_secs / 3).unwrap_or(0), 1));
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(e) =
+                dao::modify_by_heartbeat(&repo, listen_flow::service::current_owner()).await
+            {
+                event!(target: "security_api", Level::ERROR, "security_task.heartbeat {}", &e);
+            }
+        }
+    })
+}
+
+/// 依序執行同一主機的任務 (結束時釋放認領)
+async fn loop_data_market(
+    repo: &Repository,
+    limiter: &Arc<MarketLimiter>,
+    securitys: Vec<SecurityTask>,
+) {
+    let mut running = JoinSet::new();
+
+    for security in securitys {
         event!(target: "security_api", Level::DEBUG, "SecurityTask: {}", &security);
 
-        let market_type = &security.market_type;
-
-        if check_exec_date(security) {
-            let res_data = response_data::dao::find_one_by_max(security).await;
-            if res_data.is_none() {
-                let start_time = Local::now();
-
-                match loop_data_security_task(security).await {
-                    Ok(_) => {
-                        let end_time = Local::now();
-
-                        sleep(time::Duration::from_secs(sleep_time(
-                            (end_time - start_time).num_seconds(),
-                            old_market_type,
-                            market_type,
-                        )))
-                        .await;
-                        index += 1;
-                        old_market_type = &security.market_type;
+        if !check_exec_date(&security) {
+            release_data(repo, &security).await;
+            continue;
+        }
+
+        let stored_price = response_data::dao::find_one_by_max(repo, &security)
+            .await
+            .and_then(|x| serde_json::from_str::<MonthlyPrice>(&x.data_content).ok());
+        if let Some(price) = stored_price {
+            if let Err(e) = update_data(repo, &security, &FetchOutcome::Prices(price)).await {
+                event!(target: "security_api", Level::ERROR, "daily_task.get_all_task {}", &e);
+            }
+            release_data(repo, &security).await;
+            continue;
+        }
+
+        let permit = limiter.permit().await;
+        let repo = repo.clone();
+        let limiter = Arc::clone(limiter);
+        let counter = job_run::service::current_counter();
+        running.spawn(job_run::service::scope(counter, async move {
+            let _permit = permit;
+            let mut security = security;
+            let mut throttle_count = 0;
+            loop {
+                match loop_data_security_task(&repo, &limiter, &security).await {
+                    Ok(()) => {
+                        limiter.reset().await;
+                        break;
                     }
                     Err(e) => {
                         event!(target: "security_api", Level::ERROR, "daily_task.get_all_task {}", &e);
-                        continue;
+                        if !add_retry_data(&repo, &mut security, &e, &mut throttle_count).await {
+                            break;
+                        }
                     }
                 }
-            } else {
-                update_data(security, true).await;
-                index += 1;
             }
-        }
+            release_data(&repo, &security).await;
+        }));
     }
 
-    Ok(())
+    while running.join_next().await.is_some() {}
+}
+
+/// 釋放認領
+async fn release_data(repo: &Repository, security: &SecurityTask) {
+    let worker = listen_flow::service::current_owner();
+    if let Err(e) = dao::modify_by_release(repo, &security.row_id, worker).await {
+        event!(target: "security_api", Level::ERROR, "security_task.release_data {}", &e);
+    }
 }
 
 /// 檢查執行日期
 fn check_exec_date(task: &SecurityTask) -> bool {
-    let y = task.open_date_year.parse().unwrap();
-    let m = task.open_date_month.parse().unwrap();
-    let d = task.open_date_day.parse().unwrap();
-
-    let task_date = NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let task_date = task.open_date;
 
     let now_date = Local::now().date_naive();
-    let now_time = now_date.and_hms_opt(15, 30, 0).unwrap();
+    let now_time = now_date.and_time(config::service::get().market.close_time);
     let now_date_time = Local::now().naive_local();
 
-    if task_date == now_date && now_date_time > now_time {
-        return true;
-    } else if task_date != now_date {
-        return true;
-    }
-
-    false
+    task_date != now_date || now_date_time > now_time
 }
 
-/// 取得睡眠時間
-fn sleep_time(seconds: i64, old_market_type: &str, new_market_type: &str) -> u64 {
-    event!(target: "security_api", Level::DEBUG, "{0},{1},{2}", seconds, old_market_type, new_market_type);
-    match (old_market_type.as_ref(), new_market_type.as_ref()) {
-        ("上市", "上櫃") => {
-            if 4 - seconds > 0 {
-                (4 - seconds) as u64
-            } else if 4 - seconds <= 0 {
-                0
-            } else {
-                4
-            }
-        }
-        ("上市", "興櫃") => {
-            if 4 - seconds > 0 {
-                (4 - seconds) as u64
-            } else if 4 - seconds <= 0 {
-                0
-            } else {
-                4
-            }
-        }
-        ("上櫃", "上市") => {
-            if 4 - seconds > 0 {
-                (4 - seconds) as u64
-            } else if 4 - seconds <= 0 {
-                0
-            } else {
-                4
-            }
-        }
-        ("興櫃", "上市") => {
-            if 4 - seconds > 0 {
-                (4 - seconds) as u64
-            } else if 4 - seconds <= 0 {
-                0
-            } else {
-                4
-            }
-        }
-        (_, _) => {
-            if 8 - seconds > 0 {
-                (8 - seconds) as u64
-            } else if 8 - seconds <= 0 {
-                0
-            } else {
-                8
-            }
+/// 執行任務
+async fn loop_data_security_task(
+    repo: &Repository,
+    limiter: &MarketLimiter,
+    security: &SecurityTask,
+) -> Result<(), Error> {
+    // 重試設定
+    let retry_strategy = config::service::get().task.retry.strategy();
+
+    let Some(source) = market_source::service::find_source(&security.market_type) else {
+        return Ok(());
+    };
+
+    let outcome = Retry::start(retry_strategy, || async {
+        limiter.acquire().await;
+        let outcome = market_source::service::fetch(source, security).await;
+        if let Err(Error::RateLimited(reason)) = &outcome {
+            let backoff = limiter.backoff().await;
+            throttle_event::service::add_throttle_event(
+                repo,
+                source.host(),
+                security,
+                reason,
+                backoff,
+            )
+            .await;
         }
+        outcome
+    })
+    .await?;
+
+    if let FetchOutcome::Prices(price) = &outcome {
+        add_res_data(repo, security, price).await?;
     }
+    update_data(repo, security, &outcome).await
 }
 
-/// 執行任務
-async fn loop_data_security_task(security: &SecurityTask) -> Result<(), sqlx::Error> {
-    // 重試設定
-    let retry_strategy = ExponentialBackoff::from_millis(2000)
-        .max_delay(Duration::from_secs(2))
-        .take(5);
-
-    let market_type = &security.market_type;
-    let ref_market_type = market_type.as_str();
-
-    match ref_market_type {
-        "上市" => {
-            match Retry::spawn(retry_strategy, || async {
-                response_data::service::get_twse_avg_json(&security).await
-            })
-            .await
-            {
-                Ok(res) => {
-                    if !res.is_empty() && !["1", "2"].contains(&res.as_str()) {
-                        add_res_data(&security, &res).await;
-                        update_data(&security, true).await;
-                    } else if "1" == res.as_str() {
-                        update_data(&security, false).await;
-                    } else if "2" == res.as_str() {
-                        return Err(sqlx::Error::RowNotFound);
-                    }
-                    return Ok(());
-                }
-                Err(e) => return Err(sqlx::Error::Decode(e)),
-            };
-        }
-        "上櫃" => {
-            match Retry::spawn(retry_strategy, || async {
-                response_data::service::get_tpex1_json(&security).await
-            })
-            .await
-            {
-                Ok(res) => {
-                    if !res.is_empty() && !["1", "2"].contains(&res.as_str()) {
-                        add_res_data(&security, &res).await;
-                        update_data(&security, true).await;
-                    } else if "1" == res.as_str() {
-                        update_data(&security, false).await;
-                    } else if "2" == res.as_str() {
-                        return Err(sqlx::Error::RowNotFound);
-                    }
-                    return Ok(());
-                }
-                Err(e) => return Err(sqlx::Error::Decode(e)),
-            };
-        }
-        "興櫃" => {
-            match Retry::spawn(retry_strategy, || async {
-                response_data::service::get_tpex2_json(&security).await
-            })
-            .await
-            {
-                Ok(res) => {
-                    if !res.is_empty() && !["1", "2"].contains(&res.as_str()) {
-                        add_res_data(&security, &res).await;
-                        update_data(&security, true).await;
-                    } else if "1" == res.as_str() {
-                        update_data(&security, false).await;
-                    } else if "2" == res.as_str() {
-                        return Err(sqlx::Error::RowNotFound);
-                    }
-                    return Ok(());
-                }
-                Err(e) => return Err(sqlx::Error::Decode(e)),
-            };
+/// 紀錄失敗次數，超過重試或流量限制上限時標記為失敗 (回傳是否繼續重試)
+async fn add_retry_data(
+    repo: &Repository,
+    security: &mut SecurityTask,
+    error: &Error,
+    throttle_count: &mut u32,
+) -> bool {
+    let task_config = &config::service::get().task;
+
+    // 流量限制由主機佇列退避處理，不計入重試次數，連續達上限時標記為失敗
+    let exhausted = if let Error::RateLimited(_) = error {
+        *throttle_count += 1;
+        *throttle_count >= task_config.max_throttle_count
+    } else {
+        *throttle_count = 0;
+        metrics::service::add_retry(&security.market_type);
+        security.retry_count += 1;
+        security.retry_count >= task_config.max_retry_count
+    };
+
+    security.last_error = error.to_string();
+    if exhausted {
+        security.exec_status = "FAILED".to_string();
+    }
+
+    match dao::modify(repo, security.clone()).await {
+        Ok(_) => security.exec_status != "FAILED",
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "security_task.add_retry_data {}", &e);
+            false
         }
-        _ => (),
     }
+}
 
+/// 列出失敗任務 (可重新排入)
+pub async fn requeue_failed_task(
+    repo: &Repository,
+    security_codes: &[String],
+    requeue: bool,
+) -> Result<(), Error> {
+    let failed_tasks = dao::find_all_by_failed(repo, security_codes).await;
+    for task in failed_tasks {
+        event!(target: "security_api", Level::INFO, "[failed] {} {} {} {} retry {}: {}", task.open_date, task.security_code, task.security_name, task.market_type, task.retry_count, &task.last_error);
+
+        if requeue {
+            let mut security_task = task;
+            security_task.exec_status = "WAIT".to_string();
+            security_task.retry_count = 0;
+            security_task.last_error = String::new();
+            dao::modify(repo, security_task).await?;
+        }
+    }
     Ok(())
 }
 
 /// 新增回應資料
-async fn add_res_data(security: &SecurityTask, html: &str) {
-    let res_data = response_data::dao::find_one_by_min(&security).await;
-    if res_data.is_none() {
-        let new_res_data = ResponseData {
-            row_id: "".to_string(),
-            open_date_year: security.open_date_year.clone(),
-            open_date_month: security.open_date_month.clone(),
-            open_date_day: security.open_date_day.clone(),
-            exec_code: security.security_code.clone(),
-            data_content: html.to_string(),
-        };
-        response_data::dao::create(new_res_data).await.unwrap();
-    } else {
-        let existing_res_data = res_data.unwrap();
-        let new_res_data = ResponseData {
-            row_id: existing_res_data.row_id,
-            open_date_year: security.open_date_year.clone(),
-            open_date_month: security.open_date_month.clone(),
-            open_date_day: security.open_date_day.clone(),
-            exec_code: existing_res_data.exec_code,
-            data_content: html.to_string(),
-        };
-        response_data::dao::modify(new_res_data).await.unwrap();
+async fn add_res_data(
+    repo: &Repository,
+    security: &SecurityTask,
+    price: &MonthlyPrice,
+) -> Result<(), Error> {
+    let data_content = serde_json::to_string(price)?;
+
+    let res_data = response_data::dao::find_one_by_min(repo, security).await;
+    match res_data {
+        None => {
+            let new_res_data = ResponseData {
+                row_id: String::new(),
+                open_date_year: security.open_date_year.clone(),
+                open_date_month: security.open_date_month.clone(),
+                open_date_day: security.open_date_day.clone(),
+                exec_code: security.security_code.clone(),
+                data_content,
+            };
+            let cnt = response_data::dao::create(repo, new_res_data).await?;
+            job_run::service::count_inserted("response_data", cnt);
+        }
+        Some(existing_res_data) => {
+            let new_res_data = ResponseData {
+                row_id: existing_res_data.row_id,
+                open_date_year: security.open_date_year.clone(),
+                open_date_month: security.open_date_month.clone(),
+                open_date_day: security.open_date_day.clone(),
+                exec_code: existing_res_data.exec_code,
+                data_content,
+            };
+            let cnt = response_data::dao::modify(repo, new_res_data).await?;
+            job_run::service::count_updated("response_data", cnt);
+        }
     }
+    Ok(())
 }
 
 /// 更新資料
-async fn update_data(security: &SecurityTask, is_action: bool) {
+async fn update_data(
+    repo: &Repository,
+    security: &SecurityTask,
+    outcome: &FetchOutcome,
+) -> Result<(), Error> {
     let mut security_task = security.clone();
-    security_task.exec_count = security_task.exec_count + 1;
 
-    if is_action {
-        security_task.is_enabled = 0;
+    match outcome {
+        FetchOutcome::Prices(_) => {
+            security_task.exec_count += 1;
+            security_task.is_enabled = 0;
+            security_task.exec_status = "DONE".to_string();
+        }
+        FetchOutcome::NoData | FetchOutcome::Suspended => {
+            security_task.exec_count += 1;
+        }
+        FetchOutcome::Retry(reason) => {
+            return Err(Error::UpstreamRejected(format!(
+                "{} {}",
+                &security.security_code, reason
+            )));
+        }
     }
 
-    dao::modify(security_task).await.unwrap();
+    let cnt = dao::modify(repo, security_task).await?;
+    job_run::service::count_updated("security_task", cnt);
+    Ok(())
 }
@@ -15,6 +15,7 @@ pub async fn create(trax_conn: &mut PgConnection, data: SecurityTemp) -> Result<
             open_date_year
           , open_date_month
           , open_date_day
+          , open_date
           , international_code 
           , security_code 
           , security_name 
@@ -26,7 +27,7 @@ pub async fn create(trax_conn: &mut PgConnection, data: SecurityTemp) -> Result<
           , remark 
           , created_date
           , updated_date
-        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, 
+        ) VALUES ( $1, $2, $3, make_date($1::integer, $2::integer, $3::integer), $4, $5, $6, $7, 
          $8, $9, $10, $11, $12, $13, $14 )
     ",
     )
@@ -52,9 +53,8 @@ pub async fn create(trax_conn: &mut PgConnection, data: SecurityTemp) -> Result<
     }
 }
 
-pub async fn remove_all() -> Result<u64, sqlx::Error> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn remove_all(repo: &Repository) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -62,7 +62,7 @@ pub async fn remove_all() -> Result<u64, sqlx::Error> {
          WHERE 1=1
     ",
     )
-    .execute(&conn)
+    .execute(conn)
     .await
     {
         Ok(cnt) => Ok(cnt.rows_affected()),
@@ -71,6 +71,7 @@ pub async fn remove_all() -> Result<u64, sqlx::Error> {
 }
 
 pub async fn find_one(
+    repo: &Repository,
     q_year: &str,
     q_month: &str,
     q_day: &str,
@@ -78,8 +79,7 @@ pub async fn find_one(
     q_market_type: &str,
     q_issue_date: &str,
 ) -> Option<SecurityTemp> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+    let conn = &repo.connection;
 
     match sqlx::query(
         r"
@@ -126,7 +126,7 @@ pub async fn find_one(
         cfi_code: row.get("cfi_code"),
         remark: row.get("remark"),
     })
-    .fetch_optional(&conn)
+    .fetch_optional(conn)
     .await
     {
         Ok(row) => row,
@@ -137,15 +137,14 @@ pub async fn find_one(
     }
 }
 
-pub async fn find_all_by_twse(task: &DailyTask) -> Vec<SecurityTemp> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_all_by_twse(repo: &Repository, task: &DailyTask) -> Vec<SecurityTemp> {
+    let conn = &repo.connection;
 
     let q_year = task.clone().open_date_year;
     let q_month = task.clone().open_date_month;
     let q_day = task.clone().open_date_day;
-    let q_open_date = format!("{0}{1}{2}", q_year, q_month, q_day);
-    let q_issue_date = format!("{0}/{1}/{2}", q_year, q_month, q_day);
+    let q_open_date = format!("{q_year}{q_month}{q_day}");
+    let q_issue_date = format!("{q_year}/{q_month}/{q_day}");
 
     match sqlx::query(
         r"
@@ -187,7 +186,7 @@ pub async fn find_all_by_twse(task: &DailyTask) -> Vec<SecurityTemp> {
         cfi_code: row.get("cfi_code"),
         remark: row.get("remark"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
         Ok(rows) => rows,
@@ -198,15 +197,14 @@ pub async fn find_all_by_twse(task: &DailyTask) -> Vec<SecurityTemp> {
     }
 }
 
-pub async fn find_all_by_tpex(task: &DailyTask) -> Vec<SecurityTemp> {
-    let dao = Repository::new().await;
-    let conn = dao.connection;
+pub async fn find_all_by_tpex(repo: &Repository, task: &DailyTask) -> Vec<SecurityTemp> {
+    let conn = &repo.connection;
 
     let q_year = task.clone().open_date_year;
     let q_month = task.clone().open_date_month;
     let q_day = task.clone().open_date_day;
-    let q_open_date = format!("{0}{1}{2}", q_year, q_month, q_day);
-    let q_issue_date = format!("{0}/{1}/{2}", q_year, q_month, q_day);
+    let q_open_date = format!("{q_year}{q_month}{q_day}");
+    let q_issue_date = format!("{q_year}/{q_month}/{q_day}");
 
     match sqlx::query(
         r"
@@ -248,7 +246,7 @@ pub async fn find_all_by_tpex(task: &DailyTask) -> Vec<SecurityTemp> {
         cfi_code: row.get("cfi_code"),
         remark: row.get("remark"),
     })
-    .fetch_all(&conn)
+    .fetch_all(conn)
     .await
     {
         Ok(rows) => rows,
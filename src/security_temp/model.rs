@@ -35,31 +35,18 @@ impl std::fmt::Display for SecurityTemp {
 
         write!(
             f,
-            r#"{0}, 
-            open_date: {1}{2}{3}, 
-            international_code: {4}, 
-            security_code: {5}, 
-            security_name: {6}, 
-            market_type: {7}, 
-            security_type: {8}, 
-            industry_type: {9}, 
-            issue_date: {10}, 
-            cfi_code: {11}, 
-            remark: {12}, 
-            "#,
-            row_id,
-            open_date_year,
-            open_date_month,
-            open_date_day,
-            international_code,
-            security_code,
-            security_name,
-            market_type,
-            security_type,
-            industry_type,
-            issue_date,
-            cfi_code,
-            remark,
+            r"{row_id}, 
+            open_date: {open_date_year}{open_date_month}{open_date_day}, 
+            international_code: {international_code}, 
+            security_code: {security_code}, 
+            security_name: {security_name}, 
+            market_type: {market_type}, 
+            security_type: {security_type}, 
+            industry_type: {industry_type}, 
+            issue_date: {issue_date}, 
+            cfi_code: {cfi_code}, 
+            remark: {remark}, 
+            ",
         )
     }
 }
@@ -6,34 +6,33 @@ use scraper::{Html, Selector};
 use sqlx::PgConnection;
 use tracing::{event, Level};
 
-use crate::{daily_task::model::DailyTask, repository::Repository, response_data};
+use crate::{daily_task::model::DailyTask, job_run, repository::Repository, response_data, Error};
 
 use super::{dao, model::SecurityTemp};
 
-pub async fn delete_temp() -> Result<(), sqlx::Error> {
+pub async fn delete_temp(repo: &Repository) -> Result<(), Error> {
     event!(target: "security_api", Level::INFO, "call daily_task.delete_temp");
 
-    dao::remove_all().await?;
+    dao::remove_all(repo).await?;
 
     Ok(())
 }
 
-pub async fn get_security_to_temp(task: &DailyTask) -> Result<(), sqlx::Error> {
+pub async fn get_security_to_temp(repo: &Repository, task: &DailyTask) -> Result<(), Error> {
     event!(target: "security_api", Level::INFO, "call daily_task.get_security_to_temp");
-    let dao = Repository::new().await;
-    let mut conn = dao.connection.begin().await?;
+    let mut conn = repo.connection.begin().await?;
 
     let q_year = &task.open_date_year;
     let q_month = &task.open_date_month;
     let q_day = &task.open_date_day;
     let q_exec_code = "security";
 
-    let data = response_data::dao::find_one(q_year, q_month, q_day, q_exec_code).await;
-    if data.is_some() {
-        let data_content = data.unwrap().data_content;
+    let data = response_data::dao::find_one(repo, q_year, q_month, q_day, q_exec_code).await;
+    if let Some(data) = data {
+        let data_content = data.data_content;
 
-        match insert_temp_data(&mut conn, &data_content, &task).await {
-            Ok(_) => conn.commit().await?,
+        match insert_temp_data(repo, &mut conn, &data_content, task).await {
+            Ok(()) => conn.commit().await?,
             Err(_) => conn.rollback().await?,
         }
     }
@@ -42,24 +41,26 @@ pub async fn get_security_to_temp(task: &DailyTask) -> Result<(), sqlx::Error> {
 }
 
 async fn insert_temp_data(
+    repo: &Repository,
     transaction: &mut PgConnection,
     data_content: &str,
     task: &DailyTask,
-) -> Result<(), sqlx::Error> {
-    let rows = parse_table_data(data_content).unwrap();
+) -> Result<(), Error> {
+    let rows = parse_table_data(data_content)?;
     for row in rows {
         event!(target: "security_api", Level::DEBUG, "ROW: {:?}", &row);
-        loop_data_temp(transaction, &row, &task).await?;
+        loop_data_temp(repo, transaction, &row, task).await?;
     }
 
     Ok(())
 }
 
 async fn loop_data_temp(
+    repo: &Repository,
     transaction: &mut PgConnection,
     content: &HashMap<String, String>,
     task: &DailyTask,
-) -> Result<(), sqlx::Error> {
+) -> Result<(), Error> {
     let q_year = &task.open_date_year;
     let q_month = &task.open_date_month;
     let q_day = &task.open_date_day;
@@ -68,6 +69,7 @@ async fn loop_data_temp(
     let q_issue_date = content.get("7").map_or("", |v| v);
 
     let data = dao::find_one(
+        repo,
         q_year,
         q_month,
         q_day,
@@ -92,7 +94,8 @@ async fn loop_data_temp(
             cfi_code: content.get("8").map_or("", |v| v).to_string(),
             remark: content.get("9").map_or("", |v| v).to_string(),
         };
-        dao::create(transaction, security_temp).await?;
+        let cnt = dao::create(transaction, security_temp).await?;
+        job_run::service::count_inserted("security_temp", cnt);
     }
     Ok(())
 }
@@ -104,36 +107,37 @@ fn html_decode(input: &str) -> String {
         .replace("&gt;", ">")
         .replace("&quot;", "\"")
         .replace("&apos;", "'")
-        .replace("*", "")
+        .replace('*', "")
         .replace("＊", "")
 }
 
-fn parse_table_data(
-    table: &str,
-) -> Result<Vec<HashMap<String, String>>, Box<dyn std::error::Error>> {
+/// 解析代碼表 (每列以欄位序號對應內容，略過標題列)
+///
+/// # Errors
+/// 選擇器無法解析時 (沒有資料列時回傳空結果)
+pub fn parse_table_data(table: &str) -> Result<Vec<HashMap<String, String>>, Error> {
     let mut rows: Vec<HashMap<String, String>> = vec![];
 
-    let fragment = Html::parse_fragment(&table);
+    let fragment = Html::parse_fragment(table);
 
-    let tr = Selector::parse("tr").unwrap();
-    let td = Selector::parse("td").unwrap();
+    let tr = Selector::parse("tr").map_err(|e| Error::Parse(format!("selector {e}")))?;
+    let td = Selector::parse("td").map_err(|e| Error::Parse(format!("selector {e}")))?;
 
     let trs = fragment.select(&tr);
     for tr_content in trs {
-        let mut index = 0;
         let mut cells = HashMap::<String, String>::new();
 
         let tds = tr_content.select(&td);
-        for td_content in tds {
+        for (index, td_content) in tds.enumerate() {
             let a = html_decode(&td_content.inner_html());
             cells.insert(index.to_string(), a.trim().to_string());
-
-            index = index + 1;
         }
 
         rows.push(cells);
     }
-    rows.remove(0);
+    if !rows.is_empty() {
+        rows.remove(0);
+    }
 
     Ok(rows)
 }
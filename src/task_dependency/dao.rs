@@ -0,0 +1,62 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use sqlx::{postgres::PgRow, Row};
+
+use crate::repository::Repository;
+
+use super::model::TaskDependency;
+
+/// 新增工作相依 (已存在時略過)
+///
+/// # Errors
+/// 資料庫寫入失敗時回傳 `sqlx::Error`
+pub async fn create(repo: &Repository, data: TaskDependency) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        INSERT INTO task_dependency(
+            job_code
+          , depend_code
+        ) VALUES ( $1, $2 )
+        ON CONFLICT (job_code, depend_code) DO NOTHING
+    ",
+    )
+    .bind(data.job_code)
+    .bind(data.depend_code)
+    .execute(conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
+    }
+}
+
+/// 取得所有工作相依
+///
+/// # Errors
+/// 資料庫讀取失敗時回傳 `sqlx::Error`
+pub async fn find_all(repo: &Repository) -> Result<Vec<TaskDependency>, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        SELECT row_id
+             , job_code
+             , depend_code
+          FROM task_dependency
+         ORDER BY job_code, depend_code
+    ",
+    )
+    .map(|row: PgRow| TaskDependency {
+        row_id: row.get("row_id"),
+        job_code: row.get("job_code"),
+        depend_code: row.get("depend_code"),
+    })
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => Ok(rows),
+        Err(e) => Err(e),
+    }
+}
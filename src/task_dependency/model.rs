@@ -0,0 +1,24 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+#[derive(Debug, Clone)]
+pub struct TaskDependency {
+    pub row_id: String,
+    pub job_code: String,
+    pub depend_code: String,
+}
+
+impl std::fmt::Display for TaskDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let row_id = self.row_id.clone();
+        let job_code = self.job_code.clone();
+        let depend_code = self.depend_code.clone();
+
+        write!(
+            f,
+            r"{row_id}, 
+            job_code: {job_code}, 
+            depend_code: {depend_code}
+            "
+        )
+    }
+}
@@ -0,0 +1,62 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::collections::HashMap;
+
+use tracing::{event, Level};
+
+use crate::{job::service::JobRegistry, repository::Repository, Error};
+
+use super::{dao, model::TaskDependency};
+
+/// 寫入已註冊工作宣告的相依 (已存在時略過)
+///
+/// # Errors
+/// 相依的工作未註冊或資料庫寫入失敗時
+pub async fn insert_dependencies(repo: &Repository, registry: &JobRegistry) -> Result<(), Error> {
+    for job_code in registry.names() {
+        let Some(job) = registry.get(job_code) else {
+            continue;
+        };
+
+        for depend_code in job.dependencies() {
+            if !registry.contains(depend_code) {
+                return Err(Error::UnknownJob(format!(
+                    "{depend_code} (dependency of {job_code})"
+                )));
+            }
+
+            let data = TaskDependency {
+                row_id: String::new(),
+                job_code: job_code.to_string(),
+                depend_code: (*depend_code).to_string(),
+            };
+            if dao::create(repo, data).await? > 0 {
+                event!(target: "security_api", Level::INFO, "task_dependency {} -> {} added", job_code, depend_code);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 工作相依 (先寫入工作宣告的相依，再讀取 `task_dependency` 設定)
+///
+/// # Errors
+/// 相依的工作未註冊或資料庫讀寫失敗時 (不可在相依未知時平行執行)
+pub async fn find_dependencies(
+    repo: &Repository,
+    registry: &JobRegistry,
+) -> Result<HashMap<String, Vec<String>>, Error> {
+    insert_dependencies(repo, registry).await?;
+
+    let mut dependencies = HashMap::<String, Vec<String>>::new();
+
+    for data in dao::find_all(repo).await? {
+        event!(target: "security_api", Level::DEBUG, "TaskDependency: {}", &data);
+
+        dependencies
+            .entry(data.job_code)
+            .or_default()
+            .push(data.depend_code);
+    }
+    Ok(dependencies)
+}
@@ -0,0 +1,45 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use sqlx::{postgres::PgRow, Row};
+use tracing::{event, Level};
+
+use crate::repository::Repository;
+
+use super::model::TaskSetting;
+
+pub async fn find_all_by_enabled(repo: &Repository) -> Vec<TaskSetting> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        SELECT row_id
+             , group_code
+             , job_code
+             , wait_type
+             , wait_number
+             , is_enabled
+             , sort_no
+          FROM task_setting
+         WHERE is_enabled = 1
+         ORDER BY sort_no, group_code
+    ",
+    )
+    .map(|row: PgRow| TaskSetting {
+        row_id: row.get("row_id"),
+        group_code: row.get("group_code"),
+        job_code: row.get("job_code"),
+        wait_type: row.get("wait_type"),
+        wait_number: row.get("wait_number"),
+        is_enabled: row.get("is_enabled"),
+        sort_no: row.get("sort_no"),
+    })
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            event!(target: "security_api", Level::ERROR, "task_setting.find_all_by_enabled: {}", &e);
+            Vec::new()
+        }
+    }
+}
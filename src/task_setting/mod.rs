@@ -1 +1,3 @@
+pub mod dao;
 pub mod model;
+pub mod service;
@@ -0,0 +1,62 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use chrono::{
+    DateTime, Datelike, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone,
+};
+
+use super::model::TaskSetting;
+
+/// 排程基準日 (週一)，日、週、時、分、秒間隔由基準日的指定時間起算
+const ANCHOR_DATE: NaiveDate = match NaiveDate::from_ymd_opt(2000, 1, 3) {
+    Some(date) => date,
+    None => NaiveDate::MIN,
+};
+
+/// 依等待種類與數值計算晚於 `from` 的下次執行時間 (對齊指定時間，設定不正確時回傳 None)
+#[must_use]
+pub fn next_run_time(
+    setting: &TaskSetting,
+    at: NaiveTime,
+    from: DateTime<Local>,
+) -> Option<DateTime<Local>> {
+    let wait_number = setting.wait_number.filter(|x| *x > 0)?;
+    let wait_type = setting.wait_type.as_deref().unwrap_or("TS");
+    let anchor = ANCHOR_DATE.and_time(at);
+    let from = from.naive_local();
+
+    let next = match wait_type {
+        "DM" => next_month(at, from, wait_number.unsigned_abs())?,
+        "DD" => next_step(anchor, from, TimeDelta::days(i64::from(wait_number)))?,
+        "DW" => next_step(anchor, from, TimeDelta::weeks(i64::from(wait_number)))?,
+        "TH" => next_step(anchor, from, TimeDelta::hours(i64::from(wait_number)))?,
+        "TM" => next_step(anchor, from, TimeDelta::minutes(i64::from(wait_number)))?,
+        "TS" => next_step(anchor, from, TimeDelta::seconds(i64::from(wait_number)))?,
+        _ => return None,
+    };
+    Local.from_local_datetime(&next).earliest()
+}
+
+/// 基準時間加上整數倍間隔中，晚於 `from` 的第一個時間
+fn next_step(anchor: NaiveDateTime, from: NaiveDateTime, step: TimeDelta) -> Option<NaiveDateTime> {
+    let step_secs = step.num_seconds();
+    let count = (from - anchor).num_seconds().div_euclid(step_secs) + 1;
+    anchor.checked_add_signed(TimeDelta::seconds(step_secs.checked_mul(count)?))
+}
+
+/// 每隔數月的 1 日指定時間中，晚於 `from` 的第一個時間
+fn next_month(at: NaiveTime, from: NaiveDateTime, months: u32) -> Option<NaiveDateTime> {
+    let month_index = from.year() * 12 + i32::try_from(from.month0()).ok()?;
+    let aligned = month_index - month_index.rem_euclid(i32::try_from(months).ok()?);
+
+    let first_day = NaiveDate::from_ymd_opt(
+        aligned.div_euclid(12),
+        aligned.rem_euclid(12).unsigned_abs() + 1,
+        1,
+    )?
+    .and_time(at);
+    if first_day > from {
+        Some(first_day)
+    } else {
+        first_day.checked_add_months(Months::new(months))
+    }
+}
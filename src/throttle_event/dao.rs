@@ -0,0 +1,40 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use chrono::Local;
+
+use crate::repository::Repository;
+
+use super::model::ThrottleEvent;
+
+pub async fn create(repo: &Repository, data: ThrottleEvent) -> Result<u64, sqlx::Error> {
+    let conn = &repo.connection;
+
+    match sqlx::query(
+        r"
+        INSERT INTO throttle_event(
+            host
+          , market_type
+          , security_code
+          , open_date
+          , reason
+          , backoff_seconds
+          , created_date
+          , updated_date
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8 )
+    ",
+    )
+    .bind(data.host)
+    .bind(data.market_type)
+    .bind(data.security_code)
+    .bind(data.open_date)
+    .bind(data.reason)
+    .bind(data.backoff_seconds)
+    .bind(Local::now())
+    .bind(Local::now())
+    .execute(conn)
+    .await
+    {
+        Ok(cnt) => Ok(cnt.rows_affected()),
+        Err(e) => Err(e),
+    }
+}
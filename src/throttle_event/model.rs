@@ -0,0 +1,38 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use chrono::NaiveDate;
+
+#[derive(Debug, Clone)]
+pub struct ThrottleEvent {
+    pub row_id: String,
+    pub host: String,
+    pub market_type: String,
+    pub security_code: String,
+    pub open_date: NaiveDate,
+    pub reason: String,
+    pub backoff_seconds: i32,
+}
+
+impl std::fmt::Display for ThrottleEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let row_id = self.row_id.clone();
+        let host = self.host.clone();
+        let market_type = self.market_type.clone();
+        let security_code = self.security_code.clone();
+        let open_date = self.open_date;
+        let reason = self.reason.clone();
+        let backoff_seconds = self.backoff_seconds;
+
+        write!(
+            f,
+            r"{row_id}, 
+            host: {host}, 
+            market_type: {market_type}, 
+            security_code: {security_code}, 
+            open_date: {open_date}, 
+            reason: {reason}, 
+            backoff_seconds: {backoff_seconds}
+            "
+        )
+    }
+}
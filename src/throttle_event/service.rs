@@ -0,0 +1,34 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::time::Duration;
+
+use tracing::{event, Level};
+
+use crate::{metrics, repository::Repository, security_task::model::SecurityTask};
+
+use super::{dao, model::ThrottleEvent};
+
+/// 紀錄流量限制
+pub async fn add_throttle_event(
+    repo: &Repository,
+    host: &str,
+    task: &SecurityTask,
+    reason: &str,
+    backoff: Duration,
+) {
+    let throttle_event = ThrottleEvent {
+        row_id: String::new(),
+        host: host.to_string(),
+        market_type: task.market_type.clone(),
+        security_code: task.security_code.clone(),
+        open_date: task.open_date,
+        reason: reason.to_string(),
+        backoff_seconds: i32::try_from(backoff.as_secs()).unwrap_or(i32::MAX),
+    };
+    event!(target: "security_api", Level::WARN, "ThrottleEvent: {}", &throttle_event);
+    metrics::service::add_throttle(host, &task.market_type);
+
+    if let Err(e) = dao::create(repo, throttle_event).await {
+        event!(target: "security_api", Level::ERROR, "throttle_event.create: {}", &e);
+    }
+}
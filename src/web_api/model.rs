@@ -0,0 +1,45 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SecurityItem {
+    pub security_code: String,
+    pub security_name: String,
+    pub market_type: String,
+    pub issue_date: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceItem {
+    pub security_code: String,
+    pub security_name: String,
+    pub price_date: NaiveDate,
+    pub price_close: BigDecimal,
+    pub price_avg: BigDecimal,
+    pub price_hight: BigDecimal,
+    pub price_hight_avg: BigDecimal,
+    pub price_lowest: BigDecimal,
+    pub price_lowest_avg: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarItem {
+    pub ce_date: NaiveDate,
+    pub week_index: i32,
+    pub date_status: String,
+    pub group_task: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityQuery {
+    pub market_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PriceQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
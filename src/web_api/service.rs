@@ -0,0 +1,118 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use tokio::net::TcpListener;
+use tracing::{event, Level};
+
+use crate::{calendar_data, repository::Repository, security_price, security_task, Error};
+
+use super::model::{CalendarItem, PriceItem, PriceQuery, SecurityItem, SecurityQuery};
+
+/// 查詢服務
+pub async fn serve(repo: &Repository, addr: &str) -> Result<(), std::io::Error> {
+    let listener = TcpListener::bind(addr).await?;
+    event!(target: "security_api", Level::INFO, "web_api listen on {}", listener.local_addr()?);
+
+    axum::serve(listener, router(repo.clone())).await
+}
+
+/// 查詢路由
+pub fn router(repo: Repository) -> Router {
+    Router::new()
+        .route("/securities", get(get_securities))
+        .route("/securities/{code}/prices", get(get_security_prices))
+        .route("/calendar/{year}", get(get_calendar))
+        .with_state(repo)
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        match self {
+            Error::Parse(message) => (StatusCode::BAD_REQUEST, message).into_response(),
+            e => {
+                event!(target: "security_api", Level::ERROR, "web_api {}", &e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal server error").into_response()
+            }
+        }
+    }
+}
+
+/// 取得證券清單
+async fn get_securities(
+    State(repo): State<Repository>,
+    Query(query): Query<SecurityQuery>,
+) -> Result<Json<Vec<SecurityItem>>, Error> {
+    let tasks = security_task::dao::find_all_by_latest(&repo, query.market_type.as_deref()).await?;
+
+    Ok(Json(
+        tasks
+            .into_iter()
+            .map(|x| SecurityItem {
+                security_code: x.security_code,
+                security_name: x.security_name,
+                market_type: x.market_type,
+                issue_date: x.issue_date,
+            })
+            .collect(),
+    ))
+}
+
+/// 取得證券價格
+async fn get_security_prices(
+    State(repo): State<Repository>,
+    Path(code): Path<String>,
+    Query(query): Query<PriceQuery>,
+) -> Result<Json<Vec<PriceItem>>, Error> {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(Error::Parse(format!("from {from} is after to {to}")));
+        }
+    }
+
+    let prices = security_price::dao::find_all_by_range(&repo, &code, query.from, query.to).await?;
+
+    Ok(Json(
+        prices
+            .into_iter()
+            .filter_map(|x| {
+                Some(PriceItem {
+                    price_date: x.trade_date?,
+                    security_code: x.security_code,
+                    security_name: x.security_name,
+                    price_close: x.price_close,
+                    price_avg: x.price_avg,
+                    price_hight: x.price_hight,
+                    price_hight_avg: x.price_hight_avg,
+                    price_lowest: x.price_lowest,
+                    price_lowest_avg: x.price_lowest_avg,
+                })
+            })
+            .collect(),
+    ))
+}
+
+/// 取得行事曆
+async fn get_calendar(
+    State(repo): State<Repository>,
+    Path(year): Path<i32>,
+) -> Result<Json<Vec<CalendarItem>>, Error> {
+    let calendars = calendar_data::dao::find_all_by_year(&repo, year).await?;
+
+    Ok(Json(
+        calendars
+            .into_iter()
+            .map(|x| CalendarItem {
+                ce_date: x.ce_date,
+                week_index: x.week_index,
+                date_status: x.date_status,
+                group_task: x.group_task,
+            })
+            .collect(),
+    ))
+}
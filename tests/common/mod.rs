@@ -0,0 +1,165 @@
+#![warn(clippy::all, clippy::pedantic)]
+// 各測試程式僅使用部分共用函式
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    env, process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use axum::{
+    extract::{Form, Query},
+    http::header,
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use reqwest::Url;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use tokio::net::TcpListener;
+
+/// 同一測試程式建立的資料庫序號
+static DATABASE_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+/// 證交所查無資料
+const TWSE_NO_DATA: &str = r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#;
+/// 證交所錯誤頁面
+const ERROR_PAGE: &str = "<html><body>系統忙碌中</body></html>";
+/// 模擬交易所一律回傳錯誤頁面的代碼
+pub const DELISTED_CODE: &str = "9999";
+/// 櫃買中心查無資料
+const TPEX_NO_DATA: &str = r#"{"tables":[],"date":"","stat":"ok"}"#;
+
+/// 啟動模擬交易所 (重播 `tests/fixtures` 錄製的回應)，回傳位址
+pub async fn spawn_mock_exchange() -> String {
+    let app = Router::new()
+        .route("/isin/class_main.jsp", get(isin))
+        .route("/rwd/zh/afterTrading/STOCK_DAY", get(stock_day))
+        .route("/www/zh-tw/afterTrading/tradingStock", post(trading_stock))
+        .route("/www/zh-tw/emerging/historical", post(emerging_historical))
+        .route(
+            "/rwd/zh/holidaySchedule/holidaySchedule",
+            get(holiday_schedule),
+        );
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    format!("http://{addr}")
+}
+
+async fn isin() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/html; charset=MS950")],
+        include_bytes!("../fixtures/isin_class_main.html").as_slice(),
+    )
+}
+
+async fn stock_day(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+    let (content_type, body) = match params.get("stockNo").map(String::as_str) {
+        Some("2330") => (
+            "application/json",
+            include_str!("../fixtures/twse_stock_day_2330.json"),
+        ),
+        // 已下市代碼持續回傳錯誤頁面
+        Some(DELISTED_CODE) => ("text/html", ERROR_PAGE),
+        _ => ("application/json", TWSE_NO_DATA),
+    };
+    ([(header::CONTENT_TYPE, content_type)], body)
+}
+
+async fn trading_stock(Form(params): Form<HashMap<String, String>>) -> impl IntoResponse {
+    let body = match params.get("code").map(String::as_str) {
+        Some("6488") => include_str!("../fixtures/tpex_trading_stock_6488.json"),
+        _ => TPEX_NO_DATA,
+    };
+    ([(header::CONTENT_TYPE, "application/json")], body)
+}
+
+async fn emerging_historical(Form(params): Form<HashMap<String, String>>) -> impl IntoResponse {
+    let body = match params.get("code").map(String::as_str) {
+        Some("7734") => include_str!("../fixtures/tpex_emerging_historical_7734.json"),
+        _ => TPEX_NO_DATA,
+    };
+    ([(header::CONTENT_TYPE, "application/json")], body)
+}
+
+async fn holiday_schedule() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        include_str!("../fixtures/holiday_schedule_113.json"),
+    )
+}
+
+/// 可建立資料庫的管理連線 (`TEST_DATABASE_URL`)
+pub fn admin_url() -> String {
+    env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set for database tests")
+}
+
+/// 測試用資料庫 (建立後執行 migrations，結束時刪除)
+pub struct TestDatabase {
+    admin: PgPool,
+    name: String,
+    pub url: String,
+}
+
+impl TestDatabase {
+    /// 以管理連線建立資料庫
+    pub async fn create(admin_url: &str) -> Self {
+        let admin = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(admin_url)
+            .await
+            .unwrap();
+
+        let name = format!(
+            "security_api_test_{}_{}",
+            process::id(),
+            DATABASE_SEQ.fetch_add(1, Ordering::Relaxed)
+        );
+        sqlx::query(&format!("DROP DATABASE IF EXISTS {name}"))
+            .execute(&admin)
+            .await
+            .unwrap();
+        sqlx::query(&format!("CREATE DATABASE {name}"))
+            .execute(&admin)
+            .await
+            .unwrap();
+
+        let mut url = Url::parse(admin_url).unwrap();
+        url.set_path(&name);
+        let url = url.to_string();
+
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&url)
+            .await
+            .unwrap();
+        sqlx::query(r#"CREATE EXTENSION IF NOT EXISTS "uuid-ossp""#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::migrate::Migrator::new(std::path::Path::new("migrations"))
+            .await
+            .unwrap()
+            .run(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+
+        TestDatabase { admin, name, url }
+    }
+
+    /// 刪除資料庫
+    pub async fn drop(self) {
+        sqlx::query(&format!(
+            "DROP DATABASE IF EXISTS {} WITH (FORCE)",
+            self.name
+        ))
+        .execute(&self.admin)
+        .await
+        .unwrap();
+    }
+}
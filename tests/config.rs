@@ -0,0 +1,131 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard},
+};
+
+use chrono::NaiveTime;
+use security_api::{init_config, load_config, Error};
+
+/// 環境變數為程序共用，讀取設定的測試依序執行
+fn lock_env() -> MutexGuard<'static, ()> {
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+    ENV_LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+fn write_config(name: &str, content: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("security_api_{name}_{}.toml", std::process::id()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn load_example_config() {
+    let _guard = lock_env();
+    let config = load_config(Some("security_api.example.toml".as_ref())).unwrap();
+
+    assert_eq!(config.twse.host, "https://www.twse.com.tw");
+    assert_eq!(config.task.max_throttle_count, 5);
+    assert_eq!(config.task.max_exec_count, 3);
+    assert_eq!(config.database.max_connections, 10);
+}
+
+#[test]
+fn init_config_once() {
+    let _guard = lock_env();
+    init_config(Some("security_api.example.toml".as_ref())).unwrap();
+
+    let result = init_config(Some("security_api.example.toml".as_ref()));
+    assert!(matches!(result, Err(Error::Config(message)) if message.contains("already loaded")));
+}
+
+#[test]
+fn env_overrides_file() {
+    let _guard = lock_env();
+    let path = write_config(
+        "env",
+        r#"
+        [market]
+        close_time = "14:00:00"
+
+        [task]
+        max_throttle_count = 7
+
+        [database]
+        max_connections = 20
+        "#,
+    );
+    env::set_var("SECURITY_API_TASK_MAX_THROTTLE_COUNT", "9");
+    env::set_var("SECURITY_API_DATABASE_MAX_CONNECTIONS", "4");
+    let result = load_config(Some(&path));
+    env::remove_var("SECURITY_API_TASK_MAX_THROTTLE_COUNT");
+    env::remove_var("SECURITY_API_DATABASE_MAX_CONNECTIONS");
+    fs::remove_file(&path).unwrap();
+
+    let config = result.unwrap();
+    assert_eq!(
+        config.market.close_time,
+        NaiveTime::from_hms_opt(14, 0, 0).unwrap()
+    );
+    assert_eq!(config.task.max_throttle_count, 9);
+    assert_eq!(config.database.max_connections, 4);
+    assert_eq!(config.database.min_connections, 0);
+}
+
+#[test]
+fn reject_invalid_env_value() {
+    let _guard = lock_env();
+    env::set_var("SECURITY_API_DATABASE_MAX_CONNECTIONS", "many");
+    let result = load_config(None);
+    env::remove_var("SECURITY_API_DATABASE_MAX_CONNECTIONS");
+
+    assert!(matches!(
+        result,
+        Err(Error::Config(message)) if message.contains("SECURITY_API_DATABASE_MAX_CONNECTIONS")
+    ));
+}
+
+#[test]
+fn reject_invalid_values() {
+    let _guard = lock_env();
+    let path = write_config(
+        "invalid",
+        r#"
+        [twse]
+        host = "ftp://www.twse.com.tw/"
+        burst = 0
+
+        [flow]
+        lease_secs = 60
+        heartbeat_secs = 60
+
+        [database]
+        max_connections = 2
+        min_connections = 5
+        "#,
+    );
+    let result = load_config(Some(&path));
+    fs::remove_file(&path).unwrap();
+
+    let Err(Error::Config(message)) = result else {
+        panic!("expected config error");
+    };
+    assert!(message.contains("twse.host"));
+    assert!(message.contains("twse.burst"));
+    assert!(message.contains("flow.heartbeat_secs"));
+    assert!(message.contains("database.max_connections"));
+}
+
+#[test]
+fn reject_unknown_keys() {
+    let _guard = lock_env();
+    let path = write_config("unknown", "[twse]\nhots = \"https://www.twse.com.tw\"\n");
+    let result = load_config(Some(&path));
+    fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(Error::Config(message)) if message.contains("hots")));
+}
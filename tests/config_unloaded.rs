@@ -0,0 +1,23 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use security_api::{
+    backup_insert,
+    repository::{PoolOption, Repository},
+    Error,
+};
+
+/// 未載入設定時公開入口回傳設定錯誤，不在呼叫途中中止
+#[tokio::test]
+async fn entry_points_require_loaded_config() {
+    let result = Repository::connect("postgres://localhost/unused", &PoolOption::default()).await;
+    assert!(
+        matches!(&result, Err(Error::Config(message)) if message.contains("init_config")),
+        "{result:?}"
+    );
+
+    let result = Repository::new().await;
+    assert!(matches!(result, Err(Error::Config(_))), "{result:?}");
+
+    let result = backup_insert();
+    assert!(matches!(result, Err(Error::Config(_))), "{result:?}");
+}
@@ -0,0 +1,123 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+mod common;
+
+use chrono::{Datelike, NaiveDate};
+use security_api::{
+    add_daily_task, init_config,
+    repository::{PoolOption, Repository},
+    run_price_task, DailyTask, Error, Job, JobFuture, JobRegistry, TaskOption,
+};
+use sqlx::Row;
+
+/// 不做任何事的工作
+struct NoopJob;
+
+impl Job for NoopJob {
+    fn name(&self) -> &'static str {
+        "noop_job"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "price"
+    }
+
+    fn run<'a>(
+        &'a self,
+        _repo: &'a Repository,
+        _task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// 跨月的開市日
+fn open_dates() -> [NaiveDate; 4] {
+    [
+        NaiveDate::from_ymd_opt(2024, 4, 30).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 5, 31).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 6, 3).unwrap(),
+    ]
+}
+
+async fn seed(repo: &Repository, registry: &JobRegistry) {
+    for open_date in open_dates() {
+        sqlx::query(
+            r"
+            INSERT INTO calendar_data(ce_year, ce_month, ce_day, ce_date, week_index, date_status, group_task)
+            VALUES ($1, $2, $3, $4, $5, 'O', 'SECURITY')
+            ",
+        )
+        .bind(format!("{:04}", open_date.year()))
+        .bind(format!("{:02}", open_date.month()))
+        .bind(format!("{:02}", open_date.day()))
+        .bind(open_date)
+        .bind(i32::try_from(open_date.weekday().num_days_from_sunday()).unwrap())
+        .execute(&repo.connection)
+        .await
+        .unwrap();
+    }
+
+    sqlx::query(
+        r"
+        INSERT INTO task_setting(group_code, job_code, wait_type, wait_number, is_enabled, sort_no)
+        VALUES ('SECURITY', 'noop_job', 'TS', 1, 1, 1)
+        ",
+    )
+    .execute(&repo.connection)
+    .await
+    .unwrap();
+
+    for open_date in open_dates() {
+        add_daily_task(repo, registry, open_date, false)
+            .await
+            .unwrap();
+    }
+}
+
+/// 指定年月僅執行當月任務 (含月底)，格式錯誤時回傳錯誤
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn run_selected_month() {
+    init_config(None).unwrap();
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+
+    let mut registry = JobRegistry::new();
+    registry.register(NoopJob);
+    seed(&repo, &registry).await;
+
+    let invalid = TaskOption {
+        open_month: Some((2024, 13)),
+        ..TaskOption::default()
+    };
+    let result = run_price_task(&repo, &registry, &invalid).await;
+    assert!(matches!(result, Err(Error::Parse(_))), "{result:?}");
+
+    let option = TaskOption {
+        open_month: Some((2024, 5)),
+        ..TaskOption::default()
+    };
+    run_price_task(&repo, &registry, &option).await.unwrap();
+
+    let tasks = sqlx::query("SELECT open_date, exec_status FROM daily_task ORDER BY open_date")
+        .fetch_all(&repo.connection)
+        .await
+        .unwrap()
+        .iter()
+        .map(|x| (x.get::<NaiveDate, _>(0), x.get::<String, _>(1)))
+        .collect::<Vec<_>>();
+    let expected = open_dates()
+        .into_iter()
+        .zip(["WAIT", "EXIT", "EXIT", "WAIT"])
+        .map(|(date, status)| (date, status.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(tasks, expected);
+
+    repo.connection.close().await;
+    database.drop().await;
+}
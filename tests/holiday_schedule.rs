@@ -0,0 +1,137 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+mod common;
+
+use std::{env, fs};
+
+use axum::{routing::get, Router};
+use chrono::{Datelike, Local, NaiveDate};
+use security_api::{
+    add_next_year, init_config, parse_holiday_schedule,
+    repository::{PoolOption, Repository},
+    Error, HolidayDate,
+};
+use serde_json::json;
+use sqlx::Row;
+use tokio::net::TcpListener;
+
+fn find<'a>(holidays: &'a [HolidayDate], date: &str) -> Option<&'a HolidayDate> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+    holidays.iter().find(|x| x.ce_date == date)
+}
+
+#[test]
+fn parse_fixture_schedule() {
+    let content = include_str!("fixtures/holiday_schedule_113.json");
+    let holidays = parse_holiday_schedule(content).unwrap();
+
+    assert_eq!(holidays.len(), 18);
+    assert_eq!(find(&holidays, "2024-01-01").unwrap().date_status, "S");
+    assert_eq!(find(&holidays, "2024-01-02").unwrap().date_status, "O");
+    assert_eq!(find(&holidays, "2024-02-05").unwrap().date_status, "O");
+    assert_eq!(find(&holidays, "2024-02-06").unwrap().date_status, "S");
+    assert_eq!(find(&holidays, "2024-02-15").unwrap().date_status, "O");
+    assert_eq!(find(&holidays, "2024-10-10").unwrap().date_status, "S");
+}
+
+#[test]
+fn parse_makeup_saturday() {
+    let content = r#"{
+        "stat": "ok",
+        "fields": ["名稱", "日期", "星期", "說明"],
+        "data": [
+            ["補行上班日", "102/02/23", "六", "補行上班，照常交易。"],
+            ["調整放假日", "1020215", "五", "調整放假，市場無交易。"]
+        ]
+    }"#;
+    let holidays = parse_holiday_schedule(content).unwrap();
+
+    assert_eq!(find(&holidays, "2013-02-23").unwrap().date_status, "O");
+    assert_eq!(find(&holidays, "2013-02-15").unwrap().date_status, "S");
+}
+
+#[test]
+fn reject_bad_schedule() {
+    let rejected = parse_holiday_schedule(r#"{ "stat": "查詢日期大於今日" }"#);
+    assert!(matches!(rejected, Err(Error::UpstreamRejected(_))));
+
+    let bad_date = r#"{ "stat": "ok", "data": [["國慶日", "10/10", "四", ""]] }"#;
+    assert!(matches!(
+        parse_holiday_schedule(bad_date),
+        Err(Error::Parse(_))
+    ));
+}
+
+/// 當年第一個平日起的兩個平日
+fn first_weekdays(year: i32) -> (NaiveDate, NaiveDate) {
+    let mut weekdays = NaiveDate::from_ymd_opt(year, 1, 1)
+        .unwrap()
+        .iter_days()
+        .filter(|x| x.weekday().number_from_monday() < 6);
+    (weekdays.next().unwrap(), weekdays.next().unwrap())
+}
+
+/// 啟動只回傳指定休市日的開休市日期服務，回傳網址
+async fn spawn_holiday_schedule(holiday: NaiveDate) -> String {
+    let body = json!({
+        "stat": "ok",
+        "fields": ["名稱", "日期", "星期", "說明"],
+        "data": [["調整放假日", holiday.format("%Y-%m-%d").to_string(), "", "市場無交易"]],
+    })
+    .to_string();
+    let app = Router::new().route(
+        "/holidaySchedule",
+        get(move || {
+            let body = body.clone();
+            async move { body }
+        }),
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    format!("http://{addr}/holidaySchedule")
+}
+
+/// 新增年度行事曆時套用證交所公告的休市日
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn next_year_uses_holiday_schedule() {
+    let (holiday, first_open) = first_weekdays(Local::now().year());
+    let url = spawn_holiday_schedule(holiday).await;
+
+    let path = env::temp_dir().join(format!("security_api_holiday_{}.toml", std::process::id()));
+    fs::write(&path, format!("[holiday]\nurl = \"{url}\"\n")).unwrap();
+    init_config(Some(&path)).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+
+    add_next_year(&repo).await.unwrap();
+
+    let rows = sqlx::query(
+        "SELECT date_status, group_task FROM calendar_data WHERE ce_date IN ($1, $2) ORDER BY ce_date",
+    )
+    .bind(holiday)
+    .bind(first_open)
+    .fetch_all(&repo.connection)
+    .await
+    .unwrap()
+    .iter()
+    .map(|x| (x.get::<String, _>(0), x.get::<String, _>(1)))
+    .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        [
+            ("S".to_string(), "STOP".to_string()),
+            ("O".to_string(), "FIRST".to_string()),
+        ]
+    );
+
+    repo.connection.close().await;
+    database.drop().await;
+}
@@ -0,0 +1,70 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use security_api::{DailyTask, Job, JobFuture, JobRegistry, Repository, TaskOption};
+
+struct ExportCsv;
+
+impl Job for ExportCsv {
+    fn name(&self) -> &'static str {
+        "export_csv"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "price"
+    }
+
+    fn run<'a>(
+        &'a self,
+        _repo: &'a Repository,
+        _task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[test]
+fn builtin_jobs_registered() {
+    let registry = JobRegistry::new();
+
+    for name in [
+        "delete_temp",
+        "get_web_security",
+        "res_to_temp",
+        "temp_to_task",
+        "task_run",
+        "res_price",
+        "price_value",
+    ] {
+        assert!(registry.contains(name), "{name}");
+    }
+    assert!(!registry.contains("export_csv"));
+}
+
+#[test]
+fn builtin_dependencies_registered() {
+    let registry = JobRegistry::new();
+
+    for name in registry.names() {
+        for dependency in registry.get(name).unwrap().dependencies() {
+            assert!(registry.contains(dependency), "{name} -> {dependency}");
+        }
+    }
+    let task_run = registry.get("task_run").unwrap();
+    assert_eq!(task_run.dependencies(), ["temp_to_task"]);
+    assert!(registry
+        .get("delete_temp")
+        .unwrap()
+        .dependencies()
+        .is_empty());
+}
+
+#[test]
+fn register_custom_job() {
+    let mut registry = JobRegistry::new();
+    registry.register(ExportCsv);
+
+    let job = registry.get("export_csv").unwrap();
+    assert_eq!(job.flow_code(), "price");
+    assert!(job.dependencies().is_empty());
+}
@@ -0,0 +1,210 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use chrono::NaiveDate;
+use security_api::{
+    parse_close_price, parse_daily_bar, parse_table_data, parse_web_security_data, Error,
+    SecurityDailyBar,
+};
+
+fn open_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, 2).unwrap()
+}
+
+#[test]
+fn parse_isin_fixture() {
+    let page = include_bytes!("fixtures/isin_class_main.html");
+    let (html, _, _) = encoding_rs::BIG5.decode(page);
+
+    let table = parse_web_security_data(&html).unwrap();
+    let rows = parse_table_data(&table).unwrap();
+
+    assert_eq!(rows.len(), 5);
+    assert_eq!(rows[0]["2"], "2330");
+    assert_eq!(rows[0]["3"], "台積電");
+    assert_eq!(rows[0]["4"], "上市");
+    assert_eq!(rows[0]["7"], "1994/09/05");
+    assert_eq!(rows[3]["4"], "上櫃");
+    assert_eq!(rows[4]["4"], "興櫃");
+}
+
+#[test]
+fn parse_twse_fixture() {
+    let body = include_str!("fixtures/twse_stock_day_2330.json");
+    let prices = parse_close_price("上市", body, open_date()).unwrap();
+
+    assert_eq!(
+        prices,
+        [
+            ("113/05/02".to_string(), "775.00".to_string()),
+            ("113/05/03".to_string(), "782.00".to_string()),
+            ("113/05/06".to_string(), "799.00".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn parse_tpex_fixtures() {
+    let body = include_str!("fixtures/tpex_trading_stock_6488.json");
+    let prices = parse_close_price("上櫃", body, open_date()).unwrap();
+    // 無成交 (收盤 0.00) 略過
+    assert_eq!(prices.len(), 2);
+    assert_eq!(prices[1], ("113/05/03".to_string(), "494.00".to_string()));
+
+    let body = include_str!("fixtures/tpex_emerging_historical_7734.json");
+    let prices = parse_close_price("興櫃", body, open_date()).unwrap();
+    assert_eq!(prices[0], ("113/05/02".to_string(), "372.69".to_string()));
+}
+
+#[test]
+fn parse_empty_and_blocked_responses() {
+    let body = r#"{"stat":"很抱歉，沒有符合條件的資料!"}"#;
+    assert!(parse_close_price("上市", body, open_date())
+        .unwrap()
+        .is_empty());
+
+    let body = "<html><body>Access Denied</body></html>";
+    assert!(matches!(
+        parse_close_price("上市", body, open_date()),
+        Err(Error::RateLimited(message)) if message == "html response"
+    ));
+}
+
+#[test]
+fn throttle_messages_checked_outside_data() {
+    // 純文字或 stat 欄位的封鎖訊息視為限制流量
+    for body in [
+        "Too Many Requests",
+        r#"{"stat":"查詢過於頻繁，請稍後再試"}"#,
+    ] {
+        assert!(
+            matches!(
+                parse_close_price("上市", body, open_date()),
+                Err(Error::RateLimited(_))
+            ),
+            "{body}"
+        );
+    }
+
+    // 正常 JSON 的其他欄位含相同字詞時照常解析
+    let body = include_str!("fixtures/twse_stock_day_2330.json")
+        .replace("當日統計資訊", "系統暫停服務期間請稍後再試，當日統計資訊");
+    let prices = parse_close_price("上市", &body, open_date()).unwrap();
+    assert_eq!(prices.len(), 3);
+}
+
+/// 交易日期、成交股數、成交金額、開高低收、漲跌與筆數
+fn bar_values(bar: &SecurityDailyBar) -> [String; 9] {
+    [
+        bar.trade_date.to_string(),
+        bar.trade_volume.to_string(),
+        bar.trade_value.to_string(),
+        bar.price_open.to_string(),
+        bar.price_high.to_string(),
+        bar.price_low.to_string(),
+        bar.price_close.to_string(),
+        bar.price_change.to_string(),
+        bar.trade_count.to_string(),
+    ]
+}
+
+#[test]
+fn parse_twse_daily_bar() {
+    let body = include_str!("fixtures/twse_stock_day_2330.json");
+    let bars = parse_daily_bar("上市", body, open_date()).unwrap();
+
+    assert_eq!(bars.len(), 3);
+    assert_eq!(bars[0].price_date, "0113/05/02");
+    assert_eq!(
+        bar_values(&bars[0]),
+        [
+            "2024-05-02",
+            "27498412",
+            "21272374386",
+            "768.00",
+            "778.00",
+            "768.00",
+            "775.00",
+            "9.00",
+            "37181"
+        ]
+    );
+}
+
+#[test]
+fn parse_tpex_daily_bar_scaled() {
+    let body = include_str!("fixtures/tpex_trading_stock_6488.json");
+    let bars = parse_daily_bar("上櫃", body, open_date()).unwrap();
+
+    // 仟股、仟元換算為股、元，無成交 (--) 略過
+    assert_eq!(bars.len(), 2);
+    assert_eq!(
+        bar_values(&bars[0]),
+        [
+            "2024-05-02",
+            "1234000",
+            "605132000",
+            "487.00",
+            "493.50",
+            "486.00",
+            "490.50",
+            "3.50",
+            "2011"
+        ]
+    );
+    assert!(bars
+        .iter()
+        .all(|bar| bar.trade_date.to_string() != "2024-05-06"));
+
+    let body = r#"{
+        "tables": [{
+            "title": "個股日成交資訊",
+            "subtitle": "6488 環球晶 113年05月",
+            "date": "20240502",
+            "data": [["113/05/02", "1,500", "74,250", "49.00", "50.00", "48.50", "49.50", "X0.50", "321"]],
+            "fields": ["日 期", "成交張數", "成交仟元", "開盤", "最高", "最低", "收盤", "漲跌", "筆數"],
+            "notes": [],
+            "totalCount": 1,
+            "summary": []
+        }],
+        "date": "20240502",
+        "stat": "ok"
+    }"#;
+    let bars = parse_daily_bar("上櫃", body, open_date()).unwrap();
+    assert_eq!(
+        bar_values(&bars[0]),
+        [
+            "2024-05-02",
+            "1500000",
+            "74250000",
+            "49.00",
+            "50.00",
+            "48.50",
+            "49.50",
+            "0.50",
+            "321"
+        ]
+    );
+}
+
+#[test]
+fn parse_tpex_daily_bar_average_close() {
+    let body = include_str!("fixtures/tpex_emerging_historical_7734.json");
+    let bars = parse_daily_bar("興櫃", body, open_date()).unwrap();
+
+    // 興櫃無收盤價，以日均價為收盤價，股數與金額不換算
+    assert_eq!(bars.len(), 2);
+    assert_eq!(
+        bar_values(&bars[0]),
+        [
+            "2024-05-02",
+            "52000",
+            "19380000",
+            "0",
+            "378.00",
+            "365.00",
+            "372.69",
+            "0",
+            "41"
+        ]
+    );
+}
@@ -0,0 +1,167 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+mod common;
+
+use std::{env, fs};
+
+use chrono::NaiveDate;
+use security_api::{
+    add_daily_task, init_config, metrics_router,
+    repository::{PoolOption, Repository},
+    run_daily_task, JobRegistry, TaskOption,
+};
+use tokio::net::TcpListener;
+
+/// 證券流程的內建工作
+const JOB_CODES: [&str; 5] = [
+    "delete_temp",
+    "get_web_security",
+    "res_to_temp",
+    "temp_to_task",
+    "task_run",
+];
+
+/// 指向模擬交易所的設定
+fn write_config(base_url: &str) -> std::path::PathBuf {
+    let path = env::temp_dir().join(format!("security_api_metrics_{}.toml", std::process::id()));
+    let content = format!(
+        r#"
+        [twse]
+        host = "{base_url}"
+        interval_secs = 1
+
+        [tpex]
+        host = "{base_url}"
+        interval_secs = 1
+
+        [isin]
+        url = "{base_url}/isin/class_main.jsp"
+
+        [holiday]
+        url = "{base_url}/rwd/zh/holidaySchedule/holidaySchedule"
+        "#
+    );
+    fs::write(&path, content).unwrap();
+    path
+}
+
+async fn seed(repo: &Repository, open_date: NaiveDate) {
+    sqlx::query(
+        r"
+        INSERT INTO calendar_data(ce_year, ce_month, ce_day, ce_date, week_index, date_status, group_task)
+        VALUES ('2024', '05', '02', $1, 4, 'O', 'SECURITY')
+        ",
+    )
+    .bind(open_date)
+    .execute(&repo.connection)
+    .await
+    .unwrap();
+
+    for (sort_no, job_code) in (1..).zip(JOB_CODES) {
+        sqlx::query(
+            r"
+            INSERT INTO task_setting(group_code, job_code, wait_type, wait_number, is_enabled, sort_no)
+            VALUES ('SECURITY', $1, 'TS', 1, 1, $2)
+            ",
+        )
+        .bind(job_code)
+        .bind(sort_no)
+        .execute(&repo.connection)
+        .await
+        .unwrap();
+    }
+}
+
+/// 啟動指標服務，回傳位址
+async fn spawn_metrics(repo: &Repository) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = metrics_router().with_state(repo.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    format!("http://{addr}/metrics")
+}
+
+/// 取得指標數值 (以完整名稱與標籤比對)
+fn metric_value(text: &str, name: &str) -> Option<f64> {
+    text.lines()
+        .filter_map(|line| line.rsplit_once(' '))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+/// 以模擬交易所執行證券流程後檢查指標
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn metrics_after_security_flow() {
+    let base_url = common::spawn_mock_exchange().await;
+    let config_path = write_config(&base_url);
+    init_config(Some(&config_path)).unwrap();
+    fs::remove_file(&config_path).unwrap();
+
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+
+    let open_date = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    seed(&repo, open_date).await;
+
+    let registry = JobRegistry::new();
+    add_daily_task(&repo, &registry, open_date, false)
+        .await
+        .unwrap();
+    run_daily_task(&repo, &registry, &TaskOption::default())
+        .await
+        .unwrap();
+
+    let url = spawn_metrics(&repo).await;
+    let text = reqwest::get(&url).await.unwrap().text().await.unwrap();
+    let expected = [
+        (r#"security_api_http_requests_total{market="isin"}"#, 1.0),
+        (r#"security_api_http_requests_total{market="上市"}"#, 2.0),
+        (r#"security_api_http_requests_total{market="上櫃"}"#, 1.0),
+        (r#"security_api_http_requests_total{market="興櫃"}"#, 1.0),
+        (
+            r#"security_api_http_request_duration_seconds_count{market="上市"}"#,
+            2.0,
+        ),
+        (
+            r#"security_api_http_request_duration_seconds_bucket{market="上櫃",le="+Inf"}"#,
+            1.0,
+        ),
+        (
+            r#"security_api_rows_written_total{op="insert",table="response_data"}"#,
+            4.0,
+        ),
+        (
+            r#"security_api_rows_written_total{op="insert",table="security_temp"}"#,
+            5.0,
+        ),
+        (
+            r#"security_api_rows_written_total{op="insert",table="security_task"}"#,
+            4.0,
+        ),
+        (
+            r#"security_api_rows_written_total{op="update",table="security_task"}"#,
+            4.0,
+        ),
+    ];
+    for (name, value) in expected {
+        assert_eq!(metric_value(&text, name), Some(value), "{name}\n{text}");
+    }
+    for job_code in JOB_CODES {
+        let name = format!(r#"security_api_job_runs_total{{job_code="{job_code}",status="EXIT"}}"#);
+        assert_eq!(metric_value(&text, &name), Some(1.0), "{name}\n{text}");
+        let name =
+            format!(r#"security_api_job_last_success_timestamp_seconds{{job_code="{job_code}"}}"#);
+        assert!(
+            metric_value(&text, &name).is_some_and(|x| x > 0.0),
+            "{name}"
+        );
+    }
+    assert!(!text.contains(r#"status="FAIL""#), "{text}");
+
+    repo.connection.close().await;
+    database.drop().await;
+}
@@ -0,0 +1,196 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+mod common;
+
+use std::{env, fs};
+
+use chrono::NaiveDate;
+use security_api::{
+    add_daily_task, init_config,
+    repository::{PoolOption, Repository},
+    run_daily_task, run_price_task, JobRegistry, TaskOption,
+};
+use sqlx::Row;
+
+/// 依序執行的內建工作
+const JOB_CODES: [&str; 7] = [
+    "delete_temp",
+    "get_web_security",
+    "res_to_temp",
+    "temp_to_task",
+    "task_run",
+    "res_price",
+    "price_value",
+];
+
+/// 指向模擬交易所的設定
+fn write_config(base_url: &str) -> std::path::PathBuf {
+    let path = env::temp_dir().join(format!("security_api_pipeline_{}.toml", std::process::id()));
+    let content = format!(
+        r#"
+        [twse]
+        host = "{base_url}"
+        interval_secs = 1
+
+        [tpex]
+        host = "{base_url}"
+        interval_secs = 1
+
+        [isin]
+        url = "{base_url}/isin/class_main.jsp"
+
+        [holiday]
+        url = "{base_url}/rwd/zh/holidaySchedule/holidaySchedule"
+        "#
+    );
+    fs::write(&path, content).unwrap();
+    path
+}
+
+/// 行事曆與排程工作
+async fn seed(repo: &Repository, open_date: NaiveDate) {
+    sqlx::query(
+        r"
+        INSERT INTO calendar_data(ce_year, ce_month, ce_day, ce_date, week_index, date_status, group_task)
+        VALUES ('2024', '05', '02', $1, 4, 'O', 'SECURITY')
+        ",
+    )
+    .bind(open_date)
+    .execute(&repo.connection)
+    .await
+    .unwrap();
+
+    for (sort_no, job_code) in (1..).zip(JOB_CODES) {
+        sqlx::query(
+            r"
+            INSERT INTO task_setting(group_code, job_code, wait_type, wait_number, is_enabled, sort_no)
+            VALUES ('SECURITY', $1, 'TS', 1, 1, $2)
+            ",
+        )
+        .bind(job_code)
+        .bind(sort_no)
+        .execute(&repo.connection)
+        .await
+        .unwrap();
+    }
+}
+
+/// 以模擬交易所與暫時資料庫執行完整流程
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn run_pipeline_offline() {
+    let admin_url = common::admin_url();
+
+    let base_url = common::spawn_mock_exchange().await;
+    let config_path = write_config(&base_url);
+    init_config(Some(&config_path)).unwrap();
+    fs::remove_file(&config_path).unwrap();
+
+    let database = common::TestDatabase::create(&admin_url).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+
+    let open_date = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    seed(&repo, open_date).await;
+
+    let registry = JobRegistry::new();
+    let option = TaskOption::default();
+    // 試跑不寫入資料庫
+    add_daily_task(&repo, &registry, open_date, true)
+        .await
+        .unwrap();
+    assert_eq!(count(&repo, "SELECT COUNT(*) FROM daily_task").await, 0);
+
+    add_daily_task(&repo, &registry, open_date, false)
+        .await
+        .unwrap();
+    run_daily_task(&repo, &registry, &option).await.unwrap();
+    run_price_task(&repo, &registry, &option).await.unwrap();
+
+    let pending = count(
+        &repo,
+        "SELECT COUNT(*) FROM daily_task WHERE exec_status <> 'EXIT'",
+    )
+    .await;
+    assert_eq!(pending, 0);
+
+    // 權證不建立任務，查無資料的 0050 維持待執行
+    let tasks =
+        sqlx::query("SELECT security_code, exec_status FROM security_task ORDER BY security_code")
+            .fetch_all(&repo.connection)
+            .await
+            .unwrap()
+            .iter()
+            .map(|x| (x.get::<String, _>(0), x.get::<String, _>(1)))
+            .collect::<Vec<_>>();
+    assert_eq!(
+        tasks,
+        [
+            ("0050".to_string(), "WAIT".to_string()),
+            ("2330".to_string(), "DONE".to_string()),
+            ("6488".to_string(), "DONE".to_string()),
+            ("7734".to_string(), "DONE".to_string()),
+        ]
+    );
+
+    let prices = sqlx::query(
+        "SELECT security_code, COUNT(*) FROM security_price GROUP BY security_code ORDER BY security_code",
+    )
+    .fetch_all(&repo.connection)
+    .await
+    .unwrap()
+    .iter()
+    .map(|x| (x.get::<String, _>(0), x.get::<i64, _>(1)))
+    .collect::<Vec<_>>();
+    assert_eq!(
+        prices,
+        [
+            ("2330".to_string(), 3),
+            ("6488".to_string(), 2),
+            ("7734".to_string(), 2)
+        ]
+    );
+
+    // 日線交易日期由民國日期轉換
+    let sql = "SELECT COUNT(*) FROM security_daily_bar WHERE security_code = '2330' AND trade_date = '2024-05-02'";
+    assert_eq!(count(&repo, sql).await, 1);
+
+    check_price_dry_run(&repo, &registry).await;
+
+    repo.connection.close().await;
+    database.drop().await;
+}
+
+/// 試跑重轉收盤價與平均價格，僅列出將寫入的價格
+async fn check_price_dry_run(repo: &Repository, registry: &JobRegistry) {
+    for sql in [
+        "UPDATE daily_task SET exec_status = 'WAIT' WHERE job_code IN ('res_price', 'price_value')",
+        "DELETE FROM listen_flow WHERE flow_code = 'price'",
+        "DELETE FROM security_price WHERE security_code = '2330'",
+        "UPDATE security_price SET price_avg = 0 WHERE security_code = '6488'",
+    ] {
+        sqlx::query(sql).execute(&repo.connection).await.unwrap();
+    }
+
+    let dry_run = TaskOption {
+        dry_run: true,
+        ..TaskOption::default()
+    };
+    run_price_task(repo, registry, &dry_run).await.unwrap();
+
+    let sql = "SELECT COUNT(*) FROM security_price WHERE security_code = '2330'";
+    assert_eq!(count(repo, sql).await, 0);
+    let sql = "SELECT COUNT(*) FROM security_price WHERE security_code = '6488' AND price_avg = 0";
+    assert_eq!(count(repo, sql).await, 2);
+    let sql = "SELECT COUNT(*) FROM daily_task WHERE exec_status = 'WAIT'";
+    assert_eq!(count(repo, sql).await, 2);
+}
+
+async fn count(repo: &Repository, sql: &str) -> i64 {
+    sqlx::query(sql)
+        .fetch_one(&repo.connection)
+        .await
+        .unwrap()
+        .get(0)
+}
@@ -0,0 +1,84 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use chrono::NaiveDate;
+use security_api::{Error, RocDate};
+
+fn ce_date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+#[test]
+fn parse_accepted_formats() {
+    let cases = [
+        ("113/05/02", ce_date(2024, 5, 2)),
+        ("113年05月02日", ce_date(2024, 5, 2)),
+        ("1130502", ce_date(2024, 5, 2)),
+        (" 113/5/2 ", ce_date(2024, 5, 2)),
+        ("0113/05/02", ce_date(2024, 5, 2)),
+        ("99/12/31", ce_date(2010, 12, 31)),
+        ("99年12月31日", ce_date(2010, 12, 31)),
+        ("0991231", ce_date(2010, 12, 31)),
+        ("1/01/01", ce_date(1912, 1, 1)),
+        ("113/02/29", ce_date(2024, 2, 29)),
+    ];
+
+    for (text, expected) in cases {
+        let date = text
+            .parse::<RocDate>()
+            .unwrap_or_else(|e| panic!("{text}: {e}"));
+        assert_eq!(date.to_naive(), expected, "{text}");
+    }
+}
+
+#[test]
+fn parse_rejects_invalid_input() {
+    for text in [
+        "",
+        "abc",
+        "113-05-02",
+        "113/05",
+        "113/05/02/01",
+        "0502",
+        "113/13/01",
+        "113/02/30",
+        "112/02/29",
+        "113年05月02",
+        "113/aa/02",
+    ] {
+        let result = text.parse::<RocDate>();
+        assert!(matches!(result, Err(Error::Parse(_))), "{text}: {result:?}");
+    }
+}
+
+#[test]
+fn reject_year_before_roc() {
+    for text in ["0/05/02", "0年05月02日", "00000502", "-1/05/02"] {
+        let result = text.parse::<RocDate>();
+        assert!(matches!(result, Err(Error::Parse(_))), "{text}: {result:?}");
+    }
+    assert!(RocDate::from_ymd(0, 5, 2).is_err());
+    assert!(RocDate::from_ymd(-1, 5, 2).is_err());
+}
+
+#[test]
+fn round_trip_through_naive_date() {
+    let date = ce_date(2024, 5, 2);
+    let roc = RocDate::from(date);
+
+    assert_eq!(roc.year(), 113);
+    assert_eq!(roc.to_string(), "113/05/02");
+    assert_eq!(roc.year_month(), "113/05");
+    assert_eq!(roc.price_date(), "0113/05/02");
+    assert_eq!(NaiveDate::from(roc), date);
+    assert_eq!(RocDate::from_ymd(113, 5, 2).unwrap(), roc);
+
+    for text in [roc.to_string(), roc.price_date()] {
+        assert_eq!(text.parse::<RocDate>().unwrap(), roc, "{text}");
+    }
+    let mut day = ce_date(1912, 1, 1);
+    while day < ce_date(1913, 1, 1) {
+        let roc = RocDate::from(day);
+        assert_eq!(roc.to_string().parse::<RocDate>().unwrap().to_naive(), day);
+        day = day.succ_opt().unwrap();
+    }
+}
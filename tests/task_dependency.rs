@@ -0,0 +1,394 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+mod common;
+
+use std::sync::Once;
+
+use chrono::NaiveDate;
+use security_api::{
+    add_daily_task, init_config,
+    repository::{PoolOption, Repository},
+    run_price_task, DailyTask, Error, Job, JobFuture, JobRegistry, TaskOption,
+};
+use sqlx::Row;
+
+/// 固定失敗的上游工作
+struct FailJob;
+
+impl Job for FailJob {
+    fn name(&self) -> &'static str {
+        "fail_job"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "price"
+    }
+
+    fn run<'a>(
+        &'a self,
+        _repo: &'a Repository,
+        _task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(async { Err(Error::JobFailed("fail_job".to_string())) })
+    }
+}
+
+/// 執行時中止的上游工作
+struct PanicJob;
+
+impl Job for PanicJob {
+    fn name(&self) -> &'static str {
+        "panic_job"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "price"
+    }
+
+    fn run<'a>(
+        &'a self,
+        _repo: &'a Repository,
+        _task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(async { panic!("panic_job aborted") })
+    }
+}
+
+/// 證券流程的上游工作 (價格流程執行時不會執行)
+struct SecurityJob;
+
+impl Job for SecurityJob {
+    fn name(&self) -> &'static str {
+        "security_job"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "security"
+    }
+
+    fn run<'a>(
+        &'a self,
+        _repo: &'a Repository,
+        _task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// 於程式宣告相依的下游工作 (未設定於 `task_dependency`)
+struct DeclaredJob(&'static str);
+
+impl Job for DeclaredJob {
+    fn name(&self) -> &'static str {
+        "declared_job"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "price"
+    }
+
+    fn dependencies(&self) -> &[&str] {
+        std::slice::from_ref(&self.0)
+    }
+
+    fn run<'a>(
+        &'a self,
+        _repo: &'a Repository,
+        _task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// 相依上游的下游工作
+struct AfterJob;
+
+impl Job for AfterJob {
+    fn name(&self) -> &'static str {
+        "after_job"
+    }
+
+    fn flow_code(&self) -> &'static str {
+        "price"
+    }
+
+    fn run<'a>(
+        &'a self,
+        _repo: &'a Repository,
+        _task: &'a DailyTask,
+        _option: &'a TaskOption,
+    ) -> JobFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| init_config(None).unwrap());
+}
+
+fn registry() -> JobRegistry {
+    let mut registry = JobRegistry::new();
+    registry.register(FailJob);
+    registry.register(PanicJob);
+    registry.register(SecurityJob);
+    registry.register(AfterJob);
+    registry
+}
+
+/// 行事曆、排程工作與相依設定 (下游相依指定的上游工作)
+async fn seed(repo: &Repository, open_date: NaiveDate, upstream: &str) {
+    sqlx::query(
+        r"
+        INSERT INTO calendar_data(ce_year, ce_month, ce_day, ce_date, week_index, date_status, group_task)
+        VALUES ('2024', '05', '02', $1, 4, 'O', 'SECURITY')
+        ",
+    )
+    .bind(open_date)
+    .execute(&repo.connection)
+    .await
+    .unwrap();
+
+    for (sort_no, job_code) in [(1, upstream), (2, "after_job")] {
+        sqlx::query(
+            r"
+            INSERT INTO task_setting(group_code, job_code, wait_type, wait_number, is_enabled, sort_no)
+            VALUES ('SECURITY', $1, 'TS', 1, 1, $2)
+            ",
+        )
+        .bind(job_code)
+        .bind(sort_no)
+        .execute(&repo.connection)
+        .await
+        .unwrap();
+    }
+
+    sqlx::query("INSERT INTO task_dependency(job_code, depend_code) VALUES ('after_job', $1)")
+        .bind(upstream)
+        .execute(&repo.connection)
+        .await
+        .unwrap();
+}
+
+async fn task_status(repo: &Repository) -> Vec<(String, String, String)> {
+    sqlx::query("SELECT job_code, exec_status, last_error FROM daily_task ORDER BY job_code")
+        .fetch_all(&repo.connection)
+        .await
+        .unwrap()
+        .iter()
+        .map(|x| (x.get(0), x.get(1), x.get(2)))
+        .collect()
+}
+
+/// 上游失敗時下游標記為失敗 (重新執行時一併執行)
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn skipped_downstream_marked_failed() {
+    init();
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+
+    let open_date = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    seed(&repo, open_date, "fail_job").await;
+
+    let registry = registry();
+    add_daily_task(&repo, &registry, open_date, false)
+        .await
+        .unwrap();
+    let result = run_price_task(&repo, &registry, &TaskOption::default()).await;
+    assert!(matches!(result, Err(Error::JobFailed(_))));
+
+    let tasks = task_status(&repo).await;
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(
+        (tasks[0].0.as_str(), tasks[0].1.as_str()),
+        ("after_job", "FAIL")
+    );
+    assert!(
+        tasks[0].2.contains("skipped, fail_job failed"),
+        "{}",
+        tasks[0].2
+    );
+    assert_eq!(
+        (tasks[1].0.as_str(), tasks[1].1.as_str()),
+        ("fail_job", "FAIL")
+    );
+
+    repo.connection.close().await;
+    database.drop().await;
+}
+
+/// 無法讀取相依設定時中止，不執行任何工作
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn dependency_error_aborts() {
+    init();
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+
+    let open_date = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    seed(&repo, open_date, "fail_job").await;
+
+    let registry = registry();
+    add_daily_task(&repo, &registry, open_date, false)
+        .await
+        .unwrap();
+    sqlx::query("DROP TABLE task_dependency")
+        .execute(&repo.connection)
+        .await
+        .unwrap();
+    let result = run_price_task(&repo, &registry, &TaskOption::default()).await;
+    assert!(matches!(result, Err(Error::Database(_))));
+
+    let tasks = task_status(&repo).await;
+    assert!(tasks.iter().all(|x| x.1 == "WAIT"), "{tasks:?}");
+
+    repo.connection.close().await;
+    database.drop().await;
+}
+
+/// 上游中止 (panic) 時標記為失敗，下游一併標記為失敗
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn panicked_job_marked_failed() {
+    init();
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+
+    let open_date = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    seed(&repo, open_date, "panic_job").await;
+
+    let registry = registry();
+    add_daily_task(&repo, &registry, open_date, false)
+        .await
+        .unwrap();
+    let result = run_price_task(&repo, &registry, &TaskOption::default()).await;
+    assert!(
+        matches!(&result, Err(Error::JobFailed(message)) if message.contains("2 failed")),
+        "{result:?}"
+    );
+
+    let tasks = task_status(&repo).await;
+    assert_eq!(
+        (tasks[0].0.as_str(), tasks[0].1.as_str()),
+        ("after_job", "FAIL")
+    );
+    assert_eq!(
+        (tasks[1].0.as_str(), tasks[1].1.as_str()),
+        ("panic_job", "FAIL")
+    );
+    assert!(tasks[1].2.contains("panic"), "{}", tasks[1].2);
+
+    repo.connection.close().await;
+    database.drop().await;
+}
+
+/// 其他流程的上游尚未結束時下游標記為失敗，不停留在執行中
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn unfinished_dependency_marked_failed() {
+    init();
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+
+    let open_date = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    seed(&repo, open_date, "security_job").await;
+
+    let registry = registry();
+    add_daily_task(&repo, &registry, open_date, false)
+        .await
+        .unwrap();
+    let result = run_price_task(&repo, &registry, &TaskOption::default()).await;
+    assert!(matches!(result, Err(Error::JobFailed(_))), "{result:?}");
+
+    let tasks = task_status(&repo).await;
+    assert_eq!(
+        (tasks[0].0.as_str(), tasks[0].1.as_str()),
+        ("after_job", "FAIL")
+    );
+    assert!(
+        tasks[0].2.contains("skipped, security_job not finished"),
+        "{}",
+        tasks[0].2
+    );
+    assert_eq!(
+        (tasks[1].0.as_str(), tasks[1].1.as_str()),
+        ("security_job", "WAIT")
+    );
+
+    repo.connection.close().await;
+    database.drop().await;
+}
+
+/// 工作宣告的相依寫入 `task_dependency`，相依未註冊的工作時中止
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn declared_dependency_inserted() {
+    init();
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+
+    let open_date = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    seed(&repo, open_date, "fail_job").await;
+    sqlx::query(
+        r"
+        INSERT INTO task_setting(group_code, job_code, wait_type, wait_number, is_enabled, sort_no)
+        VALUES ('SECURITY', 'declared_job', 'TS', 1, 1, 3)
+        ",
+    )
+    .execute(&repo.connection)
+    .await
+    .unwrap();
+
+    let mut registry = registry();
+    registry.register(DeclaredJob("fail_job"));
+    add_daily_task(&repo, &registry, open_date, false)
+        .await
+        .unwrap();
+    let result = run_price_task(&repo, &registry, &TaskOption::default()).await;
+    assert!(matches!(result, Err(Error::JobFailed(_))), "{result:?}");
+
+    let declared: i64 = sqlx::query(
+        "SELECT COUNT(*) FROM task_dependency WHERE job_code = 'declared_job' AND depend_code = 'fail_job'",
+    )
+    .fetch_one(&repo.connection)
+    .await
+    .unwrap()
+    .get(0);
+    assert_eq!(declared, 1);
+
+    let tasks = task_status(&repo).await;
+    assert_eq!(
+        (tasks[1].0.as_str(), tasks[1].1.as_str()),
+        ("declared_job", "FAIL")
+    );
+    assert!(
+        tasks[1].2.contains("skipped, fail_job failed"),
+        "{}",
+        tasks[1].2
+    );
+
+    registry.register(DeclaredJob("missing_job"));
+    let result = run_price_task(&repo, &registry, &TaskOption::default()).await;
+    assert!(
+        matches!(&result, Err(Error::UnknownJob(message)) if message.contains("missing_job")),
+        "{result:?}"
+    );
+
+    repo.connection.close().await;
+    database.drop().await;
+}
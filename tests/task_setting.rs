@@ -0,0 +1,75 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone};
+use security_api::{next_run_time, TaskSetting};
+
+fn setting(wait_type: &str, wait_number: i32) -> TaskSetting {
+    TaskSetting {
+        row_id: None,
+        group_code: Some("SECURITY".to_string()),
+        job_code: Some("task_run".to_string()),
+        wait_type: Some(wait_type.to_string()),
+        wait_number: Some(wait_number),
+        is_enabled: Some(1),
+        sort_no: Some(1),
+    }
+}
+
+fn local(value: &str) -> DateTime<Local> {
+    let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap();
+    Local.from_local_datetime(&naive).unwrap()
+}
+
+/// 收盤時間
+fn close_time() -> NaiveTime {
+    NaiveTime::from_hms_opt(15, 30, 0).unwrap()
+}
+
+#[test]
+fn next_run_aligned_to_close_time() {
+    let cases = [
+        // 當日收盤前、收盤時與收盤後
+        ("DD", 1, "2024-05-02 10:00:00", "2024-05-02 15:30:00"),
+        ("DD", 1, "2024-05-02 15:30:00", "2024-05-03 15:30:00"),
+        ("DD", 1, "2024-05-02 16:00:00", "2024-05-03 15:30:00"),
+        // 跨月、跨年與閏日
+        ("DD", 1, "2024-05-31 16:00:00", "2024-06-01 15:30:00"),
+        ("DD", 1, "2024-12-31 23:59:59", "2025-01-01 15:30:00"),
+        ("DD", 1, "2024-02-28 16:00:00", "2024-02-29 15:30:00"),
+        ("DD", 2, "2024-05-02 16:00:00", "2024-05-04 15:30:00"),
+        ("DD", 2, "2024-05-04 16:00:00", "2024-05-06 15:30:00"),
+        // 每週一
+        ("DW", 1, "2024-05-02 16:00:00", "2024-05-06 15:30:00"),
+        ("DW", 1, "2024-05-06 15:30:00", "2024-05-13 15:30:00"),
+        // 每月 1 日
+        ("DM", 1, "2024-01-31 16:00:00", "2024-02-01 15:30:00"),
+        ("DM", 1, "2024-02-01 10:00:00", "2024-02-01 15:30:00"),
+        ("DM", 1, "2024-02-01 15:30:00", "2024-03-01 15:30:00"),
+        ("DM", 1, "2024-12-15 00:00:00", "2025-01-01 15:30:00"),
+        ("DM", 3, "2024-05-02 10:00:00", "2024-07-01 15:30:00"),
+        ("DM", 12, "2024-05-02 10:00:00", "2025-01-01 15:30:00"),
+        // 時、分、秒
+        ("TH", 2, "2024-05-02 16:00:00", "2024-05-02 17:30:00"),
+        ("TH", 2, "2024-05-31 23:45:00", "2024-06-01 01:30:00"),
+        ("TM", 15, "2024-05-02 10:07:00", "2024-05-02 10:15:00"),
+        ("TM", 15, "2024-12-31 23:50:00", "2025-01-01 00:00:00"),
+        ("TS", 30, "2024-05-02 10:00:10", "2024-05-02 10:00:30"),
+    ];
+
+    for (wait_type, wait_number, from, expected) in cases {
+        assert_eq!(
+            next_run_time(&setting(wait_type, wait_number), close_time(), local(from)),
+            Some(local(expected)),
+            "{wait_type} {wait_number} from {from}"
+        );
+    }
+}
+
+#[test]
+fn next_run_rejects_invalid_setting() {
+    let now = local("2024-05-02 10:00:00");
+
+    assert_eq!(next_run_time(&setting("DD", 0), close_time(), now), None);
+    assert_eq!(next_run_time(&setting("DD", -1), close_time(), now), None);
+    assert_eq!(next_run_time(&setting("XX", 1), close_time(), now), None);
+}
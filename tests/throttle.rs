@@ -0,0 +1,92 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+mod common;
+
+use std::{env, fs, time::Duration};
+
+use chrono::NaiveDate;
+use security_api::{
+    init_config,
+    repository::{PoolOption, Repository},
+    run_worker,
+};
+use sqlx::Row;
+
+/// 指向模擬交易所，不退避且不重試單次查詢
+fn write_config(base_url: &str) -> std::path::PathBuf {
+    let path = env::temp_dir().join(format!("security_api_throttle_{}.toml", std::process::id()));
+    let content = format!(
+        r#"
+        [twse]
+        host = "{base_url}"
+        interval_secs = 1
+        backoff_min_secs = 0
+        backoff_max_secs = 0
+
+        [task]
+        max_throttle_count = 2
+
+        [task.retry]
+        count = 0
+        "#
+    );
+    fs::write(&path, content).unwrap();
+    path
+}
+
+/// 持續回傳錯誤頁面的代碼在流量限制上限後標記為失敗，不會無限重試
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn html_response_marks_task_failed() {
+    let base_url = common::spawn_mock_exchange().await;
+    let config_path = write_config(&base_url);
+    init_config(Some(&config_path)).unwrap();
+    fs::remove_file(&config_path).unwrap();
+
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+
+    sqlx::query(
+        r"
+        INSERT INTO security_task(open_date_year, open_date_month, open_date_day, open_date, security_code, security_name, market_type, is_enabled, sort_no)
+        VALUES ('2024', '05', '02', '2024-05-02', $1, '下市股', '上市', 1, 1)
+        ",
+    )
+    .bind(common::DELISTED_CODE)
+    .execute(&repo.connection)
+    .await
+    .unwrap();
+
+    let open_date = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    tokio::time::timeout(
+        Duration::from_secs(30),
+        run_worker(&repo, Some(open_date), &[], 20),
+    )
+    .await
+    .expect("worker kept retrying a throttled task")
+    .unwrap();
+
+    let task =
+        sqlx::query("SELECT exec_status, retry_count, last_error, claimed_by FROM security_task")
+            .fetch_one(&repo.connection)
+            .await
+            .unwrap();
+    assert_eq!(task.get::<String, _>("exec_status"), "FAILED");
+    assert_eq!(task.get::<i32, _>("retry_count"), 0);
+    assert!(task
+        .get::<String, _>("last_error")
+        .contains("html response"));
+    assert_eq!(task.get::<String, _>("claimed_by"), "");
+
+    let throttles: i64 = sqlx::query("SELECT COUNT(*) FROM throttle_event")
+        .fetch_one(&repo.connection)
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(throttles, 2);
+
+    repo.connection.close().await;
+    database.drop().await;
+}
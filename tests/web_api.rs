@@ -0,0 +1,151 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+mod common;
+
+use std::sync::Once;
+
+use reqwest::StatusCode;
+use security_api::{
+    init_config,
+    repository::{PoolOption, Repository},
+    router,
+};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| init_config(None).unwrap());
+}
+
+/// 證券任務、收盤價與行事曆
+async fn seed(repo: &Repository) {
+    sqlx::query(
+        r"
+        INSERT INTO security_task(open_date_year, open_date_month, open_date_day, open_date, security_code, security_name, market_type, issue_date)
+        VALUES ('2024', '05', '02', '2024-05-02', '2330', '台積電', '上市', '1994/09/05')
+             , ('2024', '05', '03', '2024-05-03', '2330', '台積電', '上市', '1994/09/05')
+             , ('2024', '05', '02', '2024-05-02', '6488', '環球晶', '上櫃', '2015/09/25')
+        ",
+    )
+    .execute(&repo.connection)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r"
+        INSERT INTO security_price(open_date_year, open_date_month, open_date_day, open_date, security_code, security_name, price_date, trade_date, price_close)
+        VALUES ('2024', '05', '02', '2024-05-02', '2330', '台積電', '0113/04/30', '2024-04-30', 770)
+             , ('2024', '05', '02', '2024-05-02', '2330', '台積電', '0113/05/02', '2024-05-02', 780.5000)
+             , ('2024', '05', '03', '2024-05-03', '2330', '台積電', '0113/05/03', '2024-05-03', 790)
+        ",
+    )
+    .execute(&repo.connection)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r"
+        INSERT INTO calendar_data(ce_year, ce_month, ce_day, ce_date, week_index, date_status, group_task)
+        VALUES ('2023', '12', '29', '2023-12-29', 5, 'O', 'SECURITY')
+             , ('2024', '05', '02', '2024-05-02', 4, 'O', 'SECURITY')
+             , ('2024', '05', '04', '2024-05-04', 6, 'S', 'STOP')
+        ",
+    )
+    .execute(&repo.connection)
+    .await
+    .unwrap();
+}
+
+/// 啟動查詢服務，回傳位址
+async fn spawn_api(repo: &Repository) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = router(repo.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    format!("http://{addr}")
+}
+
+async fn get(url: &str) -> (StatusCode, String) {
+    let response = reqwest::get(url).await.unwrap();
+    (response.status(), response.text().await.unwrap())
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn query_endpoints() {
+    init();
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+    seed(&repo).await;
+    let base_url = spawn_api(&repo).await;
+
+    let (status, body) = get(&format!("{base_url}/securities?market_type=上市")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!([{
+            "security_code": "2330",
+            "security_name": "台積電",
+            "market_type": "上市",
+            "issue_date": "1994/09/05",
+        }])
+    );
+
+    let (status, body) = get(&format!(
+        "{base_url}/securities/2330/prices?from=2024-05-01&to=2024-05-31"
+    ))
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let prices = serde_json::from_str::<Value>(&body).unwrap();
+    let prices = prices.as_array().unwrap();
+    assert_eq!(prices.len(), 2);
+    assert_eq!(prices[0]["price_date"], "2024-05-02");
+    assert_eq!(prices[0]["price_close"], "780.5000");
+    assert_eq!(prices[1]["price_date"], "2024-05-03");
+
+    let (status, _) = get(&format!(
+        "{base_url}/securities/2330/prices?from=2024-05-31&to=2024-05-01"
+    ))
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = get(&format!("{base_url}/calendar/2024")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!([
+            { "ce_date": "2024-05-02", "week_index": 4, "date_status": "O", "group_task": "SECURITY" },
+            { "ce_date": "2024-05-04", "week_index": 6, "date_status": "S", "group_task": "STOP" },
+        ])
+    );
+
+    let (status, _) = get(&format!("{base_url}/metrics")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    repo.connection.close().await;
+    database.drop().await;
+}
+
+/// 資料庫無法使用時回傳 500 而非空清單
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn database_error_returns_server_error() {
+    init();
+    let database = common::TestDatabase::create(&common::admin_url()).await;
+    let repo = Repository::connect(&database.url, &PoolOption::default())
+        .await
+        .unwrap();
+    let base_url = spawn_api(&repo).await;
+    repo.connection.close().await;
+
+    for path in ["/securities", "/securities/2330/prices", "/calendar/2024"] {
+        let (status, _) = get(&format!("{base_url}{path}")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "{path}");
+    }
+
+    database.drop().await;
+}